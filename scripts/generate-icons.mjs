@@ -75,4 +75,14 @@ execSync(`iconutil --convert icns --output "${join(iconsDir, 'icon.icns')}" "${i
 rmSync(iconsetDir, { recursive: true });
 console.log('  icon.icns');
 
+// Generate tray icons (light variant for dark menu bars, dark variant for light ones)
+for (const variant of ['light', 'dark']) {
+  const name = `tray-${variant}.png`;
+  await sharp(readFileSync(join(iconsDir, `tray-${variant}.svg`)))
+    .resize(64, 64)
+    .png()
+    .toFile(join(iconsDir, name));
+  console.log(`  ${name} (64x64)`);
+}
+
 console.log('\nDone!');
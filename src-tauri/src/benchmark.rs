@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::error::TranslateError;
+use crate::{tokens, TranslateRequest, WarmupRequest};
+
+/// ベンチマークで翻訳する文（原文の言語, 翻訳先, 原文）。長さ・言語・文体がばらけるように選んでいる
+/// 結果を比較できるよう、内容を変える時は既存の文を書き換えずに追加する
+const BENCHMARK_SENTENCES: [(&str, &str, &str); 8] = [
+    ("en", "ja", "Thank you for your help."),
+    ("ja", "en", "明日の会議は午後三時からに変更になりました。"),
+    ("en", "ja", "The quarterly report shows that revenue grew by twelve percent, mainly driven by strong demand in overseas markets."),
+    ("zh", "en", "这家餐厅的菜很好吃，但是服务有点慢。"),
+    ("de", "en", "Könnten Sie mir bitte sagen, wie ich am schnellsten zum Hauptbahnhof komme?"),
+    ("ko", "ja", "주말에 친구들과 함께 바닷가에 가서 맛있는 해산물을 먹었어요."),
+    ("ja", "en", "本製品をご使用になる前に、必ず取扱説明書をよくお読みください。誤った使い方をすると、故障や事故の原因となるおそれがあります。なお、保証期間内であっても、お客様の過失による故障は有償修理となりますのでご了承ください。"),
+    ("en", "ja", "When the first settlers arrived in the valley, they found a wide river, dense forests, and soil so fertile that almost anything would grow. Within a generation, the small camp had become a busy market town, and travelers from distant regions came to trade grain, timber, and wool."),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct BenchmarkRequest {
+    pub provider: String,
+    pub endpoint: String,
+    pub model: String,
+    pub api_key: Option<String>,
+    pub profile_name: Option<String>,
+    pub headers: Option<HashMap<String, String>>,
+    pub temperature: Option<f32>,
+    /// `benchmark-progress` の `id`。`cancel_translation` にこのIDを渡すと残りの文を打ち切る
+    pub request_id: u64,
+}
+
+/// `benchmark-progress` イベントのペイロード（各文の翻訳を始める前と、全て終えた時に発行）
+#[derive(Debug, Serialize, Clone)]
+struct BenchmarkProgress {
+    id: u64,
+    index: usize,
+    total: usize,
+}
+
+/// 1文分の結果
+#[derive(Debug, Serialize, Clone)]
+pub struct BenchmarkResult {
+    pub source_lang: String,
+    pub target_lang: String,
+    pub source_chars: usize,
+    pub translated_text: Option<String>,
+    /// リクエストから生成完了までの時間（ミリ秒）
+    pub latency_ms: u64,
+    /// 最初のチャンク以降の生成速度（トークン/秒、概算）
+    pub tokens_per_second: Option<f32>,
+    /// 訳文のトークン数（概算）
+    pub output_tokens: usize,
+    pub error: Option<TranslateError>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BenchmarkReport {
+    pub provider: String,
+    pub model: String,
+    pub results: Vec<BenchmarkResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// ウォームアップを除いた全体の所要時間（ミリ秒）
+    pub total_ms: u64,
+    /// 成功した文の平均レイテンシ（ミリ秒）
+    pub average_latency_ms: Option<u64>,
+    /// 成功した文の合計トークン数を合計生成時間で割った生成速度
+    pub average_tokens_per_second: Option<f32>,
+}
+
+/// 組み込みの文を1つずつ翻訳し、文ごとのレイテンシと生成速度、その集計を返す（モデルの比較用）
+/// 最初にモデルを読み込ませてから計測し、キャッシュは使わない。失敗した文はエラーを記録して次に進む
+#[tauri::command]
+pub async fn run_benchmark(app: tauri::AppHandle, request: BenchmarkRequest) -> Result<BenchmarkReport, String> {
+    let total = BENCHMARK_SENTENCES.len();
+    let emit_progress = |index: usize| {
+        let _ = app.emit(
+            "benchmark-progress",
+            BenchmarkProgress {
+                id: request.request_id,
+                index,
+                total,
+            },
+        );
+    };
+
+    // 読み込み時間をレイテンシに含めないよう先にウォームアップする（失敗しても翻訳で改めてエラーになる）
+    let _ = crate::warmup_model(
+        app.clone(),
+        WarmupRequest {
+            provider: request.provider.clone(),
+            endpoint: request.endpoint.clone(),
+            model: request.model.clone(),
+            api_key: request.api_key.clone(),
+            profile_name: request.profile_name.clone(),
+            keep_alive: None,
+            headers: request.headers.clone(),
+        },
+    )
+    .await;
+
+    let started = std::time::Instant::now();
+    let mut results = Vec::with_capacity(total);
+    for (index, (source_lang, target_lang, text)) in BENCHMARK_SENTENCES.iter().enumerate() {
+        emit_progress(index);
+
+        let sentence_request = TranslateRequest {
+            text: text.to_string(),
+            source_lang: source_lang.to_string(),
+            target_lang: target_lang.to_string(),
+            provider: request.provider.clone(),
+            endpoint: request.endpoint.clone(),
+            model: request.model.clone(),
+            api_key: request.api_key.clone(),
+            profile_name: request.profile_name.clone(),
+            headers: request.headers.clone(),
+            temperature: request.temperature,
+            request_id: request.request_id,
+            bypass_cache: true,
+            ..Default::default()
+        };
+        let sentence_started = std::time::Instant::now();
+        let result = crate::run_translation(app.clone(), sentence_request, false).await;
+        let latency_ms = sentence_started.elapsed().as_millis() as u64;
+
+        let cancelled = matches!(result, Err(TranslateError::Cancelled { .. }));
+        results.push(match result {
+            Ok(response) => BenchmarkResult {
+                source_lang: source_lang.to_string(),
+                target_lang: target_lang.to_string(),
+                source_chars: text.chars().count(),
+                output_tokens: tokens::heuristic_token_count(&response.translated_text),
+                translated_text: Some(response.translated_text),
+                latency_ms,
+                tokens_per_second: response.tokens_per_second,
+                error: None,
+            },
+            Err(e) => BenchmarkResult {
+                source_lang: source_lang.to_string(),
+                target_lang: target_lang.to_string(),
+                source_chars: text.chars().count(),
+                translated_text: None,
+                latency_ms,
+                tokens_per_second: None,
+                output_tokens: 0,
+                error: Some(e),
+            },
+        });
+        if cancelled {
+            break;
+        }
+    }
+    let total_ms = started.elapsed().as_millis() as u64;
+    emit_progress(total);
+
+    let succeeded: Vec<&BenchmarkResult> = results.iter().filter(|result| result.error.is_none()).collect();
+    let average_latency_ms = (!succeeded.is_empty())
+        .then(|| succeeded.iter().map(|result| result.latency_ms).sum::<u64>() / succeeded.len() as u64);
+    // 生成速度は文ごとの値の平均ではなく、合計トークン数 ÷ 合計生成時間で求める（短い文に引っ張られないように）
+    let (total_tokens, total_seconds) = succeeded
+        .iter()
+        .filter_map(|result| {
+            let rate = result.tokens_per_second.filter(|rate| *rate > 0.0)?;
+            let tokens = result.output_tokens as f32;
+            Some((tokens, tokens / rate))
+        })
+        .fold((0.0, 0.0), |(tokens, seconds), (t, s)| (tokens + t, seconds + s));
+    let average_tokens_per_second = (total_seconds > 0.0).then(|| total_tokens / total_seconds);
+
+    Ok(BenchmarkReport {
+        provider: request.provider,
+        model: request.model,
+        succeeded: succeeded.len(),
+        failed: results.len() - succeeded.len(),
+        results,
+        total_ms,
+        average_latency_ms,
+        average_tokens_per_second,
+    })
+}
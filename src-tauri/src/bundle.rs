@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
+use zip::write::SimpleFileOptions;
+
+use crate::favorites::{self, Favorite, FavoritesLock};
+use crate::settings::{self, AppConfig};
+use crate::templates::{self, PromptKind};
+use crate::{preferences, storage, temperatures};
+
+/// バンドル形式のバージョン。互換性のない変更を加えたら上げる
+/// - 1: 設定・履歴（フロントエンド）とお気に入り
+/// - 2: バックエンドの設定（`config.json`・プロンプトテンプレート・言語ペアの指示・モデルごとの温度）を追加
+const BUNDLE_VERSION: u32 = 2;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const SETTINGS_ENTRY: &str = "settings.json";
+const HISTORY_ENTRY: &str = "history.json";
+const FAVORITES_ENTRY: &str = "favorites.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleManifest {
+    version: u32,
+    app_version: String,
+    created_at: u64,
+}
+
+/// インポート結果。設定と履歴はlocalStorageで管理されているためフロントエンドに返して復元させる
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportedData {
+    pub settings: serde_json::Value,
+    pub history: serde_json::Value,
+    pub favorites_count: usize,
+}
+
+/// バックエンドが設定ディレクトリに保存している設定（バージョン2以降）
+#[derive(Debug, Clone)]
+struct BackendData {
+    config: AppConfig,
+    /// 種類ごとのプロンプトテンプレート。無い種類は既定のプロンプトを使う
+    templates: HashMap<&'static str, String>,
+    language_pair_prompts: HashMap<String, String>,
+    model_temperatures: HashMap<String, f32>,
+}
+
+/// バンドルの中身。`backend` はバージョン1のバンドルでは None
+#[derive(Debug, Clone)]
+struct Bundle {
+    settings: serde_json::Value,
+    history: serde_json::Value,
+    favorites: Vec<Favorite>,
+    backend: Option<BackendData>,
+}
+
+async fn pick_path(app: &tauri::AppHandle, save: bool) -> Result<Option<PathBuf>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let dialog = app
+        .dialog()
+        .file()
+        .add_filter("Translator backup", &["zip"]);
+
+    if save {
+        dialog
+            .set_file_name("translator-backup.zip")
+            .save_file(move |path| {
+                let _ = tx.send(path);
+            });
+    } else {
+        dialog.pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+    }
+
+    let path = rx.await.map_err(|e| format!("Failed to open file dialog: {}", e))?;
+    path.map(|p| p.into_path().map_err(|e| format!("Invalid file path: {}", e)))
+        .transpose()
+}
+
+fn write_entry<W: Write + Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    name: &str,
+    value: &impl Serialize,
+) -> Result<(), String> {
+    let content = serde_json::to_vec_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+    write_bytes(zip, name, &content)
+}
+
+fn write_bytes<W: Write + Seek>(zip: &mut zip::ZipWriter<W>, name: &str, content: &[u8]) -> Result<(), String> {
+    zip.start_file(name, SimpleFileOptions::default())
+        .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    zip.write_all(content)
+        .map_err(|e| format!("Failed to write {}: {}", name, e))
+}
+
+/// エントリーを文字列として読む。無ければ None
+fn read_text<R: Read + Seek>(zip: &mut zip::ZipArchive<R>, name: &str) -> Result<Option<String>, String> {
+    let mut entry = match zip.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", name, e)),
+    };
+    let mut content = String::new();
+    entry
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    Ok(Some(content))
+}
+
+fn read_entry<R: Read + Seek, T: serde::de::DeserializeOwned>(
+    zip: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<T, String> {
+    let content = read_text(zip, name)?.ok_or_else(|| format!("Invalid backup: missing {}", name))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid backup: failed to parse {}: {}", name, e))
+}
+
+/// 設定はオブジェクト、履歴はオブジェクトの配列でなければならない（localStorageに戻す前に形を確かめる）
+fn validate_frontend_data(settings: &serde_json::Value, history: &serde_json::Value) -> Result<(), String> {
+    if !settings.is_object() {
+        return Err(format!("Invalid backup: {} must be an object", SETTINGS_ENTRY));
+    }
+    match history.as_array() {
+        Some(items) if items.iter().all(serde_json::Value::is_object) => Ok(()),
+        _ => Err(format!("Invalid backup: {} must be an array of objects", HISTORY_ENTRY)),
+    }
+}
+
+/// バンドルをZIPとして書き出す
+fn write_bundle<W: Write + Seek>(writer: W, manifest: &BundleManifest, bundle: &Bundle) -> Result<(), String> {
+    let mut zip = zip::ZipWriter::new(writer);
+    write_entry(&mut zip, MANIFEST_ENTRY, manifest)?;
+    write_entry(&mut zip, SETTINGS_ENTRY, &bundle.settings)?;
+    write_entry(&mut zip, HISTORY_ENTRY, &bundle.history)?;
+    write_entry(&mut zip, FAVORITES_ENTRY, &bundle.favorites)?;
+    if let Some(backend) = &bundle.backend {
+        write_entry(&mut zip, settings::CONFIG_FILE, &backend.config)?;
+        for (name, template) in &backend.templates {
+            write_bytes(&mut zip, name, template.as_bytes())?;
+        }
+        write_entry(&mut zip, templates::LANGUAGE_PAIR_PROMPTS_FILE, &backend.language_pair_prompts)?;
+        write_entry(&mut zip, temperatures::MODEL_TEMPERATURES_FILE, &backend.model_temperatures)?;
+    }
+    zip.finish().map_err(|e| format!("Failed to finish backup: {}", e))?;
+    Ok(())
+}
+
+/// ZIPからバンドルを読み、全てのエントリーの形を確かめる（ここで失敗した場合は何も復元しない）
+fn read_bundle<R: Read + Seek>(reader: R) -> Result<Bundle, String> {
+    let mut zip = zip::ZipArchive::new(reader).map_err(|e| format!("Invalid backup file: {}", e))?;
+
+    let manifest: BundleManifest = read_entry(&mut zip, MANIFEST_ENTRY)?;
+    if manifest.version == 0 || manifest.version > BUNDLE_VERSION {
+        return Err(format!(
+            "Unsupported backup version {} (expected {}). It was created by Translator {}.",
+            manifest.version, BUNDLE_VERSION, manifest.app_version
+        ));
+    }
+
+    let settings: serde_json::Value = read_entry(&mut zip, SETTINGS_ENTRY)?;
+    let history: serde_json::Value = read_entry(&mut zip, HISTORY_ENTRY)?;
+    validate_frontend_data(&settings, &history)?;
+    let favorites: Vec<Favorite> = read_entry(&mut zip, FAVORITES_ENTRY)?;
+
+    let backend = if manifest.version >= 2 {
+        let config: serde_json::Value = read_entry(&mut zip, settings::CONFIG_FILE)?;
+        let config = settings::from_value(config)
+            .map_err(|e| format!("Invalid backup: {}: {}", settings::CONFIG_FILE, e))?;
+        let mut prompt_templates = HashMap::new();
+        for kind in PromptKind::ALL {
+            if let Some(template) = read_text(&mut zip, kind.file_name())? {
+                templates::validate_template(&template)
+                    .map_err(|e| format!("Invalid backup: {}: {}", kind.file_name(), e))?;
+                prompt_templates.insert(kind.file_name(), template);
+            }
+        }
+        let language_pair_prompts = read_entry(&mut zip, templates::LANGUAGE_PAIR_PROMPTS_FILE)?;
+        let model_temperatures: HashMap<String, f32> =
+            read_entry(&mut zip, temperatures::MODEL_TEMPERATURES_FILE)?;
+        if let Some((pattern, temperature)) =
+            model_temperatures.iter().find(|(_, t)| !(0.0..=2.0).contains(*t))
+        {
+            return Err(format!(
+                "Invalid backup: temperature for {} must be between 0 and 2: {}",
+                pattern, temperature
+            ));
+        }
+        Some(BackendData {
+            config,
+            templates: prompt_templates,
+            language_pair_prompts,
+            model_temperatures,
+        })
+    } else {
+        None
+    };
+
+    Ok(Bundle {
+        settings,
+        history,
+        favorites,
+        backend,
+    })
+}
+
+/// 設定・履歴・お気に入りに、バックエンドの設定（`config.json`・プロンプトテンプレート・言語ペアの指示・モデルごとの温度）を加えて
+/// 1つのZIPにまとめて保存する。ダイアログがキャンセルされた場合は None
+#[tauri::command]
+pub async fn export_data(
+    app: tauri::AppHandle,
+    settings: serde_json::Value,
+    history: serde_json::Value,
+) -> Result<Option<String>, String> {
+    let Some(path) = pick_path(&app, true).await? else {
+        return Ok(None);
+    };
+
+    let favorites = {
+        let state = app.state::<FavoritesLock>();
+        let _guard = state.0.lock().map_err(|e| format!("Failed to lock favorites: {}", e))?;
+        favorites::load_favorites(&app)?
+    };
+    let backend = BackendData {
+        config: settings::read(&app)?,
+        templates: PromptKind::ALL
+            .into_iter()
+            .filter_map(|kind| templates::load_template(&app, kind).map(|template| (kind.file_name(), template)))
+            .collect(),
+        language_pair_prompts: templates::load_language_pair_prompts(&app),
+        model_temperatures: temperatures::load_overrides(&app),
+    };
+
+    let manifest = BundleManifest {
+        version: BUNDLE_VERSION,
+        app_version: app.package_info().version.to_string(),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+    let bundle = Bundle {
+        settings,
+        history,
+        favorites,
+        backend: Some(backend),
+    };
+
+    let file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    write_bundle(file, &manifest, &bundle)?;
+
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// バックエンドの設定を復元する。ユーザー設定はキャッシュ（`PreferencesState`）ごと置き換える
+/// ショートカット・HTTPサーバーなど起動時に適用する設定は次回起動時に反映される
+async fn restore_backend(app: &tauri::AppHandle, backend: BackendData) -> Result<(), String> {
+    let BackendData {
+        config,
+        templates: mut prompt_templates,
+        language_pair_prompts,
+        model_temperatures,
+    } = backend;
+
+    settings::update(app, |current| {
+        current.profiles = config.profiles;
+        Ok(())
+    })?;
+    preferences::update(app, |prefs| *prefs = config.preferences)?;
+    for kind in PromptKind::ALL {
+        templates::set_prompt_template(app.clone(), kind, prompt_templates.remove(kind.file_name())).await?;
+    }
+    storage::write_json(
+        &storage::config_file(app, templates::LANGUAGE_PAIR_PROMPTS_FILE)?,
+        &language_pair_prompts,
+    )?;
+    storage::write_json(
+        &storage::config_file(app, temperatures::MODEL_TEMPERATURES_FILE)?,
+        &model_temperatures,
+    )
+}
+
+/// バンドルを読み込み、お気に入りとバックエンドの設定を復元して設定・履歴を返す。ダイアログがキャンセルされた場合は None
+/// バージョン1のバンドルにはバックエンドの設定が無いため、現在の設定をそのまま残す
+#[tauri::command]
+pub async fn import_data(app: tauri::AppHandle) -> Result<Option<ImportedData>, String> {
+    let Some(path) = pick_path(&app, false).await? else {
+        return Ok(None);
+    };
+
+    let file = File::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let bundle = read_bundle(file)?;
+
+    {
+        let state = app.state::<FavoritesLock>();
+        let _guard = state.0.lock().map_err(|e| format!("Failed to lock favorites: {}", e))?;
+        favorites::save_favorites(&app, &bundle.favorites)?;
+    }
+    if let Some(backend) = bundle.backend {
+        restore_backend(&app, backend).await?;
+    }
+
+    Ok(Some(ImportedData {
+        settings: bundle.settings,
+        history: bundle.history,
+        favorites_count: bundle.favorites.len(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn manifest(version: u32) -> BundleManifest {
+        BundleManifest {
+            version,
+            app_version: "1.1.0".to_string(),
+            created_at: 0,
+        }
+    }
+
+    fn bundle() -> Bundle {
+        let mut config = AppConfig::default();
+        config.preferences.always_on_top = true;
+        config.preferences.explain_shortcut = Some("Ctrl+Alt+E".to_string());
+        Bundle {
+            settings: serde_json::json!({ "targetLang": "ja" }),
+            history: serde_json::json!([{ "id": "1", "source_text": "hello", "translated_text": "こんにちは" }]),
+            favorites: Vec::new(),
+            backend: Some(BackendData {
+                config,
+                templates: HashMap::from([(
+                    PromptKind::Translation.file_name(),
+                    "Translate {source} to {target}: {text}".to_string(),
+                )]),
+                language_pair_prompts: HashMap::from([("ja-en".to_string(), "Use US spelling.".to_string())]),
+                model_temperatures: HashMap::from([("qwen".to_string(), 0.2)]),
+            }),
+        }
+    }
+
+    fn write(manifest: &BundleManifest, bundle: &Bundle) -> Cursor<Vec<u8>> {
+        let mut buffer = Cursor::new(Vec::new());
+        write_bundle(&mut buffer, manifest, bundle).unwrap();
+        buffer.set_position(0);
+        buffer
+    }
+
+    #[test]
+    fn round_trips_backend_settings() {
+        let read = read_bundle(write(&manifest(BUNDLE_VERSION), &bundle())).unwrap();
+        let backend = read.backend.unwrap();
+        assert!(backend.config.preferences.always_on_top);
+        assert_eq!(backend.config.preferences.explain_shortcut.as_deref(), Some("Ctrl+Alt+E"));
+        assert_eq!(
+            backend.templates.get(PromptKind::Translation.file_name()).map(String::as_str),
+            Some("Translate {source} to {target}: {text}")
+        );
+        assert!(!backend.templates.contains_key(PromptKind::Explanation.file_name()));
+        assert_eq!(backend.language_pair_prompts["ja-en"], "Use US spelling.");
+        assert_eq!(backend.model_temperatures["qwen"], 0.2);
+        assert_eq!(read.history.as_array().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn version_1_bundle_has_no_backend_settings() {
+        let bundle = Bundle {
+            backend: None,
+            ..bundle()
+        };
+        let read = read_bundle(write(&manifest(1), &bundle)).unwrap();
+        assert!(read.backend.is_none());
+    }
+
+    #[test]
+    fn rejects_settings_that_are_not_an_object() {
+        let bundle = Bundle {
+            settings: serde_json::json!([1, 2]),
+            ..bundle()
+        };
+        let error = read_bundle(write(&manifest(BUNDLE_VERSION), &bundle)).unwrap_err();
+        assert!(error.contains(SETTINGS_ENTRY), "{}", error);
+    }
+
+    #[test]
+    fn rejects_history_that_is_not_an_array_of_objects() {
+        for history in [serde_json::json!({ "id": "1" }), serde_json::json!(["hello"])] {
+            let bundle = Bundle {
+                history,
+                ..bundle()
+            };
+            let error = read_bundle(write(&manifest(BUNDLE_VERSION), &bundle)).unwrap_err();
+            assert!(error.contains(HISTORY_ENTRY), "{}", error);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_template_and_temperature() {
+        let mut invalid_template = bundle();
+        if let Some(backend) = invalid_template.backend.as_mut() {
+            backend
+                .templates
+                .insert(PromptKind::Explanation.file_name(), "no placeholders".to_string());
+        }
+        assert!(read_bundle(write(&manifest(BUNDLE_VERSION), &invalid_template)).is_err());
+
+        let mut invalid_temperature = bundle();
+        if let Some(backend) = invalid_temperature.backend.as_mut() {
+            backend.model_temperatures.insert("llama".to_string(), 5.0);
+        }
+        assert!(read_bundle(write(&manifest(BUNDLE_VERSION), &invalid_temperature)).is_err());
+    }
+
+    #[test]
+    fn rejects_newer_bundle_version() {
+        let error = read_bundle(write(&manifest(BUNDLE_VERSION + 1), &bundle())).unwrap_err();
+        assert!(error.contains("Unsupported backup version"), "{}", error);
+    }
+}
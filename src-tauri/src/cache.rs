@@ -0,0 +1,190 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use tauri::Manager;
+
+use crate::TranslateRequest;
+
+const CACHE_CAPACITY: usize = 200;
+
+/// 同じ原文の再翻訳でモデルを呼ばないための翻訳結果のキャッシュ（メモリ上のみ、LRUで上限を超えたら古いものから削除）
+pub struct TranslationCache(Mutex<LruCache>);
+
+impl TranslationCache {
+    pub fn new() -> Self {
+        Self(Mutex::new(LruCache::new(CACHE_CAPACITY)))
+    }
+}
+
+struct LruCache {
+    capacity: usize,
+    entries: HashMap<u64, String>,
+    // 先頭が最も古い
+    order: VecDeque<u64>,
+}
+
+impl LruCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn touch(&mut self, key: u64) {
+        if let Some(pos) = self.order.iter().position(|k| *k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(key);
+    }
+
+    fn get(&mut self, key: u64) -> Option<String> {
+        let value = self.entries.get(&key).cloned()?;
+        self.touch(key);
+        Some(value)
+    }
+
+    fn insert(&mut self, key: u64, value: String) {
+        self.entries.insert(key, value);
+        self.touch(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// 翻訳結果に影響する項目からキーを作る
+/// `prompt` は実際に送るプロンプト（テンプレート・言語ペアの指示・文体・文脈・例を反映したもの）で、
+/// それ以外に接続先・Ollamaの設定・追加ヘッダー・後処理の有無・出力上限も含める
+pub fn cache_key(request: &TranslateRequest, prompt: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    prompt.hash(&mut hasher);
+    request.text.hash(&mut hasher);
+    request.source_lang.hash(&mut hasher);
+    request.target_lang.hash(&mut hasher);
+    request.provider.hash(&mut hasher);
+    request.endpoint.hash(&mut hasher);
+    request.model.hash(&mut hasher);
+    request.use_chat_api.hash(&mut hasher);
+    // HashMapは順序が決まらないため、キー順に並べてから文字列にする
+    request
+        .ollama_options
+        .as_ref()
+        .map(|options| serde_json::to_string(&options.iter().collect::<BTreeMap<_, _>>()).unwrap_or_default())
+        .hash(&mut hasher);
+    request
+        .headers
+        .as_ref()
+        .map(|headers| headers.iter().collect::<BTreeMap<_, _>>())
+        .hash(&mut hasher);
+    request.normalize_spacing.hash(&mut hasher);
+    request.strip_preamble.hash(&mut hasher);
+    request.preamble_phrases.hash(&mut hasher);
+    request.trim_output.hash(&mut hasher);
+    request.temperature.map(f32::to_bits).hash(&mut hasher);
+    request.seed.hash(&mut hasher);
+    request.json_mode.hash(&mut hasher);
+    request.max_tokens.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn get(app: &tauri::AppHandle, key: u64) -> Option<String> {
+    let state = app.state::<TranslationCache>();
+    let mut cache = state.0.lock().ok()?;
+    cache.get(key)
+}
+
+pub fn insert(app: &tauri::AppHandle, key: u64, translation: String) {
+    let state = app.state::<TranslationCache>();
+    if let Ok(mut cache) = state.0.lock() {
+        cache.insert(key, translation);
+    };
+}
+
+pub fn clear(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<TranslationCache>();
+    let mut cache = state
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to lock translation cache: {}", e))?;
+    cache.clear();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn clear_translation_cache(app: tauri::AppHandle) -> Result<(), String> {
+    clear(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> TranslateRequest {
+        TranslateRequest {
+            text: "Hello".to_string(),
+            source_lang: "en".to_string(),
+            target_lang: "ja".to_string(),
+            provider: "ollama".to_string(),
+            endpoint: "http://localhost:11434".to_string(),
+            model: "qwen".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_request_and_prompt_share_a_key() {
+        assert_eq!(cache_key(&request(), "prompt"), cache_key(&request(), "prompt"));
+    }
+
+    #[test]
+    fn prompt_changes_the_key() {
+        // テンプレートや言語ペアの指示を変えた場合
+        assert_ne!(cache_key(&request(), "prompt"), cache_key(&request(), "edited prompt"));
+    }
+
+    #[test]
+    fn endpoint_changes_the_key() {
+        let other = TranslateRequest {
+            endpoint: "http://gpu-server:11434".to_string(),
+            ..request()
+        };
+        assert_ne!(cache_key(&request(), "prompt"), cache_key(&other, "prompt"));
+    }
+
+    #[test]
+    fn ollama_settings_change_the_key() {
+        let with_options = TranslateRequest {
+            ollama_options: Some(HashMap::from([("num_ctx".to_string(), serde_json::json!(8192))])),
+            ..request()
+        };
+        let chat_api = TranslateRequest {
+            use_chat_api: true,
+            ..request()
+        };
+        assert_ne!(cache_key(&request(), "prompt"), cache_key(&with_options, "prompt"));
+        assert_ne!(cache_key(&request(), "prompt"), cache_key(&chat_api, "prompt"));
+    }
+
+    #[test]
+    fn option_order_does_not_change_the_key() {
+        let options = |pairs: [(&str, i64); 2]| TranslateRequest {
+            ollama_options: Some(pairs.iter().map(|(k, v)| (k.to_string(), serde_json::json!(v))).collect()),
+            ..request()
+        };
+        assert_eq!(
+            cache_key(&options([("num_ctx", 8192), ("top_k", 40)]), "prompt"),
+            cache_key(&options([("top_k", 40), ("num_ctx", 8192)]), "prompt"),
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+
+/// 文末とみなす記号（CJKは空白が無いため句読点のみで区切る）
+const SENTENCE_TERMINATORS: [char; 6] = ['.', '!', '?', '。', '！', '？'];
+
+/// 文末記号の直後に続く閉じ括弧・引用符（文に含める）
+const CLOSING_MARKS: [char; 9] = ['"', '\'', ')', ']', '」', '』', '）', '”', '’'];
+
+/// 長文翻訳用に分割したテキストの1区切り
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TextChunk {
+    pub text: String,
+    /// 段落の最後のチャンクなら true（結合時に空行を入れる）
+    pub paragraph_end: bool,
+}
+
+/// 段落内の各文の終端（バイト位置）を返す
+fn sentence_ends(paragraph: &str) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut chars = paragraph.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c == '\n' {
+            ends.push(i + 1);
+            continue;
+        }
+        if !SENTENCE_TERMINATORS.contains(&c) {
+            continue;
+        }
+
+        let mut end = i + c.len_utf8();
+        // 「?!」「。」」のような連続した記号はまとめて文末に含める
+        while let Some(&(j, next)) = chars.peek() {
+            if SENTENCE_TERMINATORS.contains(&next) || CLOSING_MARKS.contains(&next) {
+                end = j + next.len_utf8();
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        // ASCIIの句読点は直後が空白の場合のみ文末とする（3.14 や example.com を分割しない）
+        if c.is_ascii() {
+            if let Some(&(_, next)) = chars.peek() {
+                if !next.is_whitespace() {
+                    continue;
+                }
+            }
+        }
+
+        ends.push(end);
+    }
+
+    if ends.last() != Some(&paragraph.len()) {
+        ends.push(paragraph.len());
+    }
+    ends
+}
+
+/// 段落を文単位に分割する
+pub fn split_sentences(paragraph: &str) -> Vec<&str> {
+    let mut start = 0;
+    sentence_ends(paragraph)
+        .into_iter()
+        .map(|end| {
+            let sentence = &paragraph[start..end];
+            start = end;
+            sentence
+        })
+        .filter(|sentence| !sentence.trim().is_empty())
+        .collect()
+}
+
+/// 上限を超える1文を分割する。上限の後半に空白があればそこで区切り、無ければ文字数で区切る
+fn split_long_sentence(sentence: &str, max_chars: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut rest: Vec<char> = sentence.trim().chars().collect();
+
+    while rest.len() > max_chars {
+        let cut = rest[max_chars / 2..max_chars]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .map(|pos| max_chars / 2 + pos + 1)
+            .unwrap_or(max_chars);
+        let piece: String = rest.drain(..cut).collect();
+        pieces.push(piece.trim().to_string());
+    }
+
+    let last: String = rest.into_iter().collect();
+    if !last.trim().is_empty() {
+        pieces.push(last.trim().to_string());
+    }
+    pieces
+}
+
+/// テキストを段落・文の境界で `max_chars` 文字以下のチャンクに分割する
+pub fn split_into_chunks(text: &str, max_chars: usize) -> Vec<TextChunk> {
+    let max_chars = max_chars.max(1);
+    let normalized = text.replace("\r\n", "\n");
+    let mut chunks = Vec::new();
+
+    for paragraph in normalized.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let mut pieces = Vec::new();
+        let mut current = String::new();
+        let mut current_len = 0;
+
+        for sentence in split_sentences(paragraph) {
+            let len = sentence.chars().count();
+
+            if current_len > 0 && current_len + len > max_chars {
+                pieces.push(current.trim().to_string());
+                current.clear();
+                current_len = 0;
+            }
+
+            if len > max_chars {
+                pieces.extend(split_long_sentence(sentence, max_chars));
+            } else {
+                current.push_str(sentence);
+                current_len += len;
+            }
+        }
+
+        if !current.trim().is_empty() {
+            pieces.push(current.trim().to_string());
+        }
+
+        let count = pieces.len();
+        chunks.extend(pieces.into_iter().enumerate().map(|(i, text)| TextChunk {
+            text,
+            paragraph_end: i + 1 == count,
+        }));
+    }
+
+    chunks
+}
+
+/// 前のチャンクの最後の文を、次のチャンクを翻訳する際の文脈として返す（最大 `max_chars` 文字）
+pub fn overlap_context(previous: &str, max_chars: usize) -> String {
+    let last = split_sentences(previous).last().copied().unwrap_or_default().trim();
+    let count = last.chars().count();
+    let context: String = last.chars().skip(count.saturating_sub(max_chars)).collect();
+    context.trim().to_string()
+}
+
+/// 文末の「.」を文の区切りとみなさない略語（小文字で比較）
+const ABBREVIATIONS: [&str; 10] = ["mr", "mrs", "ms", "dr", "prof", "st", "jr", "sr", "vs", "no"];
+
+/// 「.」の直前の語が略語、または "U.S." "e.g." のような1文字の区切りかどうか
+fn is_abbreviation(before: &str) -> bool {
+    let word = before
+        .rsplit(|c: char| c.is_whitespace() || c == '.' || c == '(' || c == '"')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    ABBREVIATIONS.contains(&word.as_str())
+        || (word.chars().count() == 1 && word.chars().all(char::is_alphabetic))
+}
+
+/// ストリーミング中のテキストで確定した最初の文末（バイト位置）を返す
+/// ASCIIの句読点は次の文字が届くまで判断を保留する
+fn confirmed_sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c == '\n' {
+            return Some(i + 1);
+        }
+        if !SENTENCE_TERMINATORS.contains(&c) {
+            continue;
+        }
+
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, next)) = chars.peek() {
+            if SENTENCE_TERMINATORS.contains(&next) || CLOSING_MARKS.contains(&next) {
+                end = j + next.len_utf8();
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        if !c.is_ascii() {
+            return Some(end);
+        }
+        match chars.peek() {
+            // 続きがまだ届いていない
+            None => return None,
+            Some(&(_, next)) if !next.is_whitespace() => continue,
+            _ if c == '.' && is_abbreviation(&text[..i]) => continue,
+            _ => return Some(end),
+        }
+    }
+
+    None
+}
+
+/// ストリーミングで届くテキストを文単位に区切るバッファ（読み上げなどに文ごとに渡すため）
+#[derive(Debug, Default)]
+pub struct SentenceBuffer {
+    pending: String,
+}
+
+impl SentenceBuffer {
+    /// テキストを追加し、新たに確定した文を返す
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        self.pending.push_str(text);
+
+        let mut sentences = Vec::new();
+        while let Some(end) = confirmed_sentence_end(&self.pending) {
+            let sentence = self.pending[..end].trim().to_string();
+            self.pending.drain(..end);
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+        }
+        sentences
+    }
+
+    /// ストリーム終了時に残りの未完の文を返す
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.pending);
+        let rest = rest.trim();
+        (!rest.is_empty()).then(|| rest.to_string())
+    }
+}
+
+/// 細かく届くテキストを一定の文字数までまとめてから渡すバッファ（チャンクのイベントを減らすため）
+/// `min_chars` が0の場合は届いたテキストをそのまま返す
+#[derive(Debug, Default)]
+pub struct ChunkBuffer {
+    min_chars: usize,
+    pending: String,
+    pending_chars: usize,
+}
+
+impl ChunkBuffer {
+    pub fn new(min_chars: usize) -> Self {
+        Self {
+            min_chars,
+            ..Default::default()
+        }
+    }
+
+    /// テキストを追加し、`min_chars` に達していればまとめたテキストを返す
+    pub fn push(&mut self, text: &str) -> Option<String> {
+        self.pending.push_str(text);
+        self.pending_chars += text.chars().count();
+        if self.pending_chars < self.min_chars {
+            return None;
+        }
+        self.finish()
+    }
+
+    /// ストリーム終了時などに残りのテキストを返す
+    pub fn finish(&mut self) -> Option<String> {
+        self.pending_chars = 0;
+        let rest = std::mem::take(&mut self.pending);
+        (!rest.is_empty()).then_some(rest)
+    }
+}
@@ -0,0 +1,245 @@
+use std::io::{IsTerminal, Write};
+
+use crate::{languages, profiles, provider, temperatures, templates, TranslateRequest};
+
+// tauri.conf.json の identifier（Tauriの app_config_dir と同じ場所を参照するため）
+const APP_IDENTIFIER: &str = "com.translator.app";
+
+// プロファイルが無い場合の既定値（フロントエンドの初期設定と同じ）
+pub(crate) const DEFAULT_PROVIDER: &str = "ollama";
+pub(crate) const DEFAULT_ENDPOINT: &str = "http://localhost:11434";
+pub(crate) const DEFAULT_MODEL: &str = "llama3";
+
+const USAGE: &str = "Usage: local-translator --text <TEXT> --to <LANG> [--from <LANG>] [--profile <NAME>]";
+
+#[derive(Debug, Default)]
+struct CliArgs {
+    text: Option<String>,
+    to: Option<String>,
+    from: Option<String>,
+    profile: Option<String>,
+}
+
+/// 翻訳用のフラグが無ければ None（通常のGUI起動）
+fn parse_args(args: &[String]) -> Option<Result<CliArgs, String>> {
+    if !args.iter().any(|arg| arg == "--text" || arg == "--to") {
+        return None;
+    }
+
+    let mut parsed = CliArgs::default();
+    let mut iter = args.iter().skip(1);
+    while let Some(flag) = iter.next() {
+        let slot = match flag.as_str() {
+            "--text" => &mut parsed.text,
+            "--to" => &mut parsed.to,
+            "--from" => &mut parsed.from,
+            "--profile" => &mut parsed.profile,
+            _ => return Some(Err(format!("Unknown argument: {}", flag))),
+        };
+        match iter.next() {
+            Some(value) => *slot = Some(value.clone()),
+            None => return Some(Err(format!("Missing value for {}", flag))),
+        }
+    }
+    Some(Ok(parsed))
+}
+
+/// アプリの設定ディレクトリ内のファイルのパス
+fn config_dir() -> Result<std::path::PathBuf, String> {
+    Ok(dirs::config_dir()
+        .ok_or("Failed to resolve config dir")?
+        .join(APP_IDENTIFIER))
+}
+
+fn config_path(file_name: &str) -> Result<std::path::PathBuf, String> {
+    Ok(config_dir()?.join(file_name))
+}
+
+fn build_request(args: CliArgs) -> Result<TranslateRequest, String> {
+    let text = args.text.filter(|t| !t.trim().is_empty()).ok_or("--text is required")?;
+    let target_lang = args.to.ok_or("--to is required")?;
+
+    let profile = profiles::load_profile_from(&config_dir()?, args.profile.as_deref())?;
+
+    let mut request = TranslateRequest {
+        text,
+        source_lang: args.from.unwrap_or_else(|| languages::AUTO.to_string()),
+        target_lang,
+        provider: DEFAULT_PROVIDER.to_string(),
+        endpoint: DEFAULT_ENDPOINT.to_string(),
+        model: DEFAULT_MODEL.to_string(),
+        ..Default::default()
+    };
+    if let Some(profile) = profile {
+        request.provider = profile.provider;
+        request.endpoint = profile.endpoint;
+        request.model = profile.model;
+        request.api_key = profile.api_key;
+    }
+    Ok(request)
+}
+
+// 端末に書き出す時に、生成中であることを示すために末尾に出すカーソル
+const LIVE_CURSOR: &str = "▌";
+
+/// 訳文のチャンクを受け取るたびに標準出力へ書き出す
+/// 最後にまとめて出力した場合と同じ結果になるよう、前後の空白と入力の区切りマーカーは書き出さない
+/// （空白やマーカーの途中かもしれない末尾は、続きが届くまで手元に残す）
+struct StdoutStream {
+    stdout: std::io::Stdout,
+    // 取り除くマーカー（インジェクション対策が無効なら空）
+    markers: Vec<&'static str>,
+    // 端末ならカーソルを出す。パイプ・リダイレクト先には訳文だけを書く
+    live: bool,
+    cursor_shown: bool,
+    // 1文字でも書き出したか（先頭の空白を読み飛ばすため）
+    started: bool,
+    // まだ書き出していない末尾
+    pending: String,
+}
+
+impl StdoutStream {
+    fn new(injection_guard: bool) -> Self {
+        let stdout = std::io::stdout();
+        Self {
+            live: stdout.is_terminal(),
+            stdout,
+            markers: if injection_guard {
+                vec![crate::INPUT_START_MARKER, crate::INPUT_END_MARKER]
+            } else {
+                Vec::new()
+            },
+            cursor_shown: false,
+            started: false,
+            pending: String::new(),
+        }
+    }
+
+    /// チャンクを追加し、確定した部分を書き出す。書き込めなくなったら（パイプの相手が終了したなど）false
+    fn push(&mut self, chunk: &str) -> bool {
+        self.pending.push_str(chunk);
+        self.strip_markers();
+
+        // マーカーの先頭と一致する末尾と、その手前の空白は続きが届くまで残す
+        let held = self
+            .markers
+            .iter()
+            .flat_map(|marker| (1..marker.len()).filter(|&len| self.pending.ends_with(&marker[..len])))
+            .max()
+            .unwrap_or(0);
+        let ready = self.pending[..self.pending.len() - held].trim_end().len();
+        let text: String = self.pending.drain(..ready).collect();
+        self.write(&text, self.live).is_ok()
+    }
+
+    /// 残りを書き出して改行で終える
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.strip_markers();
+        let text = std::mem::take(&mut self.pending);
+        self.write(text.trim_end(), false)?;
+        writeln!(self.stdout)
+    }
+
+    /// エラーで打ち切る時に、カーソルを消して書きかけの行を終える
+    fn abort(&mut self) {
+        let started = self.started;
+        if self.write("", false).is_ok() && started {
+            let _ = writeln!(self.stdout);
+        }
+    }
+
+    fn strip_markers(&mut self) {
+        for marker in &self.markers {
+            if self.pending.contains(marker) {
+                self.pending = self.pending.replace(marker, "");
+            }
+        }
+    }
+
+    fn write(&mut self, text: &str, show_cursor: bool) -> std::io::Result<()> {
+        let text = if self.started { text } else { text.trim_start() };
+        if text.is_empty() && show_cursor == self.cursor_shown {
+            return Ok(());
+        }
+        if self.cursor_shown {
+            // カーソルの1文字分戻って行末まで消す
+            write!(self.stdout, "\x08\x1b[K")?;
+            self.cursor_shown = false;
+        }
+        self.started |= !text.is_empty();
+        write!(self.stdout, "{}", text)?;
+        if show_cursor {
+            write!(self.stdout, "{}", LIVE_CURSOR)?;
+            self.cursor_shown = true;
+        }
+        self.stdout.flush()
+    }
+}
+
+/// 翻訳し、訳文を生成されたそばから標準出力に書き出す
+async fn translate(request: TranslateRequest) -> Result<(), String> {
+    let client = provider::streaming_client()?;
+    let pair_prompts = config_path(templates::LANGUAGE_PAIR_PROMPTS_FILE)
+        .map(|path| templates::load_language_pair_prompts_from(&path))
+        .unwrap_or_default();
+    let prompt = crate::build_translation_prompt(&request, None, &pair_prompts);
+    let temperature_overrides = config_path(temperatures::MODEL_TEMPERATURES_FILE)
+        .map(|path| temperatures::load_overrides_from(&path))
+        .unwrap_or_default();
+    let temperature =
+        temperatures::resolve(&request.provider, &request.model, request.temperature, &temperature_overrides);
+
+    let provider = provider::for_name(
+        &request.provider,
+        provider::ProviderConfig {
+            client,
+            endpoint: request.endpoint,
+            model: request.model,
+            api_key: request.api_key,
+            stall_timeout: std::time::Duration::from_secs(crate::DEFAULT_STALL_TIMEOUT_SECS),
+            cancel: None,
+            max_tokens: request.max_tokens,
+            temperature,
+            seed: request.seed,
+            json_mode: false,
+            headers: provider::parse_headers(request.headers.as_ref())?,
+            choices: None,
+        },
+        provider::OllamaSettings::default(),
+    );
+
+    let mut out = StdoutStream::new(request.injection_guard);
+    let result = provider
+        .stream(prompt, crate::TRANSLATION_SYSTEM_PROMPT, &mut |chunk: &str| out.push(chunk))
+        .await;
+    match result {
+        Ok(_) => out
+            .finish()
+            .map_err(|e| format!("Failed to write to stdout: {}", e)),
+        Err(e) => {
+            out.abort();
+            Err(e.into())
+        }
+    }
+}
+
+/// 起動引数に翻訳用のフラグがあれば、ウィンドウを開かずに翻訳して標準出力に逐次書き出す。
+/// 戻り値はプロセスの終了コード（GUIを起動する場合は None）
+pub fn run_from_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().collect();
+    let result = parse_args(&args)?
+        .and_then(build_request)
+        .map(|request| tauri::async_runtime::block_on(translate(request)));
+
+    match result {
+        Ok(Ok(())) => Some(0),
+        Ok(Err(e)) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            Some(2)
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// モデル呼び出しのエラー。フロントエンドが`kind`で種類を見分けて表示を変えられるようにする
+/// 例: `{"kind": "http_status", "code": 500, "message": "API error: 500 ..."}`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TranslateError {
+    /// サーバーに接続できない（未起動・エンドポイントの誤りなど）
+    ConnectionFailed { message: String },
+    /// 応答が返らない、またはストリームが途中で止まった
+    Timeout { message: String },
+    /// サーバーがエラーステータスを返した
+    HttpStatus { code: u16, message: String },
+    /// 指定したモデルがサーバーに無い
+    ModelNotFound { message: String },
+    /// レスポンスを解釈できない
+    ParseError { message: String },
+    /// ユーザーがキャンセルした
+    Cancelled { message: String },
+    /// 上記以外（プロファイルや設定値の誤りなど）
+    Other { message: String },
+}
+
+impl TranslateError {
+    pub fn message(&self) -> &str {
+        match self {
+            Self::ConnectionFailed { message }
+            | Self::Timeout { message }
+            | Self::HttpStatus { message, .. }
+            | Self::ModelNotFound { message }
+            | Self::ParseError { message }
+            | Self::Cancelled { message }
+            | Self::Other { message } => message,
+        }
+    }
+
+    /// リクエスト送信時・受信中のreqwestのエラーを分類する（`context`はメッセージの接頭辞）
+    pub fn from_reqwest(context: &str, e: reqwest::Error) -> Self {
+        let message = format!("{}: {}", context, e);
+        if e.is_timeout() {
+            Self::Timeout { message }
+        } else if e.is_decode() {
+            Self::ParseError { message }
+        } else {
+            Self::ConnectionFailed { message }
+        }
+    }
+
+    /// HTTPエラー応答を分類する。404でモデルが無い旨のメッセージなら`ModelNotFound`にする
+    /// Ollama: "model 'xxx' not found" / OpenAI互換: "The model `xxx` does not exist"
+    pub fn from_status(status: reqwest::StatusCode, message: String) -> Self {
+        let lower = message.to_lowercase();
+        if status == reqwest::StatusCode::NOT_FOUND
+            && lower.contains("model")
+            && (lower.contains("not found") || lower.contains("does not exist"))
+        {
+            Self::ModelNotFound { message }
+        } else {
+            Self::HttpStatus {
+                code: status.as_u16(),
+                message,
+            }
+        }
+    }
+}
+
+impl fmt::Display for TranslateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for TranslateError {}
+
+// 既存のヘルパーが返す文字列のエラーは`Other`として扱う
+impl From<String> for TranslateError {
+    fn from(message: String) -> Self {
+        Self::Other { message }
+    }
+}
+
+// 文字列のエラーを返すコマンド（OCRなど）からも`?`で呼べるようにする
+impl From<TranslateError> for String {
+    fn from(e: TranslateError) -> Self {
+        e.to_string()
+    }
+}
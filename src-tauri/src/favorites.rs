@@ -0,0 +1,95 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::storage;
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+/// お気に入りファイルの読み書きを直列化するためのロック
+pub struct FavoritesLock(pub Mutex<()>);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Favorite {
+    pub id: String,
+    pub source_text: String,
+    pub translated_text: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AddFavoriteRequest {
+    pub source_text: String,
+    pub translated_text: String,
+    pub source_lang: String,
+    pub target_lang: String,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub fn load_favorites(app: &tauri::AppHandle) -> Result<Vec<Favorite>, String> {
+    storage::read_json(&storage::data_file(app, FAVORITES_FILE)?)
+}
+
+pub fn save_favorites(app: &tauri::AppHandle, favorites: &[Favorite]) -> Result<(), String> {
+    storage::write_json(&storage::data_file(app, FAVORITES_FILE)?, &favorites)
+}
+
+#[tauri::command]
+pub async fn add_favorite(
+    app: tauri::AppHandle,
+    request: AddFavoriteRequest,
+) -> Result<Favorite, String> {
+    let state = app.state::<FavoritesLock>();
+    let _guard = state.0.lock().map_err(|e| format!("Failed to lock favorites: {}", e))?;
+
+    let mut favorites = load_favorites(&app)?;
+
+    // 同じ原文・翻訳先言語の組み合わせは重複して保存しない
+    if let Some(existing) = favorites
+        .iter()
+        .find(|f| f.source_text == request.source_text && f.target_lang == request.target_lang)
+    {
+        return Ok(existing.clone());
+    }
+
+    let favorite = Favorite {
+        id: now_millis().to_string(),
+        source_text: request.source_text,
+        translated_text: request.translated_text,
+        source_lang: request.source_lang,
+        target_lang: request.target_lang,
+        created_at: now_millis(),
+    };
+    favorites.insert(0, favorite.clone());
+    save_favorites(&app, &favorites)?;
+
+    Ok(favorite)
+}
+
+#[tauri::command]
+pub async fn remove_favorite(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let state = app.state::<FavoritesLock>();
+    let _guard = state.0.lock().map_err(|e| format!("Failed to lock favorites: {}", e))?;
+
+    let mut favorites = load_favorites(&app)?;
+    favorites.retain(|f| f.id != id);
+    save_favorites(&app, &favorites)
+}
+
+#[tauri::command]
+pub async fn list_favorites(app: tauri::AppHandle) -> Result<Vec<Favorite>, String> {
+    let state = app.state::<FavoritesLock>();
+    let _guard = state.0.lock().map_err(|e| format!("Failed to lock favorites: {}", e))?;
+
+    load_favorites(&app)
+}
@@ -0,0 +1,337 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::error::TranslateError;
+use crate::TranslateRequest;
+
+// ドロップされたファイルの上限（翻訳対象のテキストとしては十分な大きさ）
+const MAX_DROPPED_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// ドロップされたファイルの種類（拡張子で判定し、字幕はタイムコードを残して翻訳する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Text,
+    Srt,
+    Vtt,
+}
+
+impl FileKind {
+    fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("srt") => Self::Srt,
+            Some("vtt") => Self::Vtt,
+            _ => Self::Text,
+        }
+    }
+}
+
+/// `file-dropped` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+struct DroppedFile {
+    path: String,
+    file_name: String,
+    kind: FileKind,
+    text: String,
+}
+
+/// `subtitle-translation-progress` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+struct SubtitleProgress {
+    id: u64,
+    index: usize,
+    total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubtitleTranslateResponse {
+    pub translated_text: String,
+    pub cues: usize,
+}
+
+/// BOMで文字コードを判定してUTF-8の文字列にする（BOMは取り除く）
+/// BOMが無ければUTF-8として読む。Windowsのメモ帳などで保存したUTF-16はBOM付きなのでここで判別できる
+fn decode_text(bytes: &[u8], path: &Path) -> Result<String, String> {
+    let Some((encoding, bom_length)) = encoding_rs::Encoding::for_bom(bytes) else {
+        return String::from_utf8(bytes.to_vec())
+            .map_err(|_| format!("{} is not a text file (invalid UTF-8)", path.display()));
+    };
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes[bom_length..])
+        .map(|text| text.into_owned())
+        .ok_or_else(|| format!("{} is not a text file (invalid {})", path.display(), encoding.name()))
+}
+
+/// ファイルを読み、テキストでなければ（バイナリファイルなど）エラーにする
+fn read_text_file(path: &Path) -> Result<String, String> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    if size > MAX_DROPPED_FILE_BYTES {
+        return Err(format!(
+            "{} is too large ({} bytes, max {} bytes)",
+            path.display(),
+            size,
+            MAX_DROPPED_FILE_BYTES
+        ));
+    }
+
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let text = decode_text(&bytes, path)?;
+    // 文字列として読めてもNUL文字を含むものはテキストとして扱わない（BOM無しのUTF-16もここで弾かれる）
+    if text.contains('\0') {
+        return Err(format!("{} is not a text file (contains binary data)", path.display()));
+    }
+    Ok(text)
+}
+
+/// メインウィンドウにドロップされた最初のファイルを読み、`file-dropped`（失敗時は `file-drop-failed`）を送る
+pub fn handle_drop(window: &tauri::Window, paths: &[PathBuf]) {
+    if window.label() != "main" {
+        return;
+    }
+    let Some(path) = paths.first().cloned() else {
+        return;
+    };
+
+    let app = window.app_handle().clone();
+    // 大きなファイルの読み込みでイベントループを止めないよう別スレッドで読む
+    std::thread::spawn(move || match read_text_file(&path) {
+        Ok(text) => {
+            let _ = app.emit(
+                "file-dropped",
+                DroppedFile {
+                    file_name: path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    kind: FileKind::from_path(&path),
+                    path: path.to_string_lossy().to_string(),
+                    text,
+                },
+            );
+        }
+        Err(e) => {
+            let _ = app.emit("file-drop-failed", e);
+        }
+    });
+}
+
+/// 字幕ファイルの1行分、または1つのキューの本文
+#[derive(Debug, PartialEq)]
+enum Segment {
+    /// キュー番号・タイムコード・空行・ヘッダーなど、そのまま残す行
+    Verbatim(String),
+    /// キューの本文（複数行の場合は改行で連結）
+    Text(String),
+}
+
+/// SRT / WebVTTを、翻訳するキューの本文とそのまま残す行に分ける
+/// タイムコード（`-->`を含む行）の後から空行までを本文とみなし、それ以外（番号・`WEBVTT`ヘッダー・`NOTE`など）は残す
+fn split_subtitles(text: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut cue_text: Vec<&str> = Vec::new();
+    let mut in_cue = false;
+
+    for line in text.lines() {
+        if in_cue {
+            if line.trim().is_empty() {
+                segments.push(Segment::Text(cue_text.join("\n")));
+                cue_text.clear();
+                in_cue = false;
+                segments.push(Segment::Verbatim(line.to_string()));
+            } else {
+                cue_text.push(line);
+            }
+        } else {
+            in_cue = line.contains("-->");
+            segments.push(Segment::Verbatim(line.to_string()));
+        }
+    }
+    if !cue_text.is_empty() {
+        segments.push(Segment::Text(cue_text.join("\n")));
+    }
+    segments
+}
+
+/// 分けた行を元の改行コードで連結し直す（元のファイルが改行で終わっていれば最後にも付ける）
+fn join_segments(segments: &[Segment], original: &str) -> String {
+    let newline = if original.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut result = segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Verbatim(line) => line.replace('\n', newline),
+            Segment::Text(text) => text.replace('\n', newline),
+        })
+        .collect::<Vec<_>>()
+        .join(newline);
+    if original.ends_with('\n') {
+        result.push_str(newline);
+    }
+    result
+}
+
+/// 字幕ファイル（`request.text` に内容全体）のキューの本文だけを1つずつ翻訳し、番号とタイムコードはそのまま残す
+/// 直前のキューの原文を文脈として渡し、キューごとに `subtitle-translation-progress` を発行する
+#[tauri::command]
+pub async fn translate_subtitles(
+    app: tauri::AppHandle,
+    request: TranslateRequest,
+) -> Result<SubtitleTranslateResponse, TranslateError> {
+    let mut segments = split_subtitles(&request.text);
+    let total = segments
+        .iter()
+        .filter(|segment| matches!(segment, Segment::Text(_)))
+        .count();
+
+    let mut index = 0;
+    let mut previous: Option<String> = None;
+    for segment in segments.iter_mut() {
+        let Segment::Text(text) = segment else {
+            continue;
+        };
+        let _ = app.emit(
+            "subtitle-translation-progress",
+            SubtitleProgress {
+                id: request.request_id,
+                index,
+                total,
+            },
+        );
+
+        let cue_request = TranslateRequest {
+            text: text.clone(),
+            preceding_text: previous.take(),
+            alternatives: None,
+            ..request.clone()
+        };
+        let response = crate::run_translation(app.clone(), cue_request, false).await?;
+        // 訳文に空行が入るとキューの区切りになってしまうため詰める
+        let translated = response
+            .translated_text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        previous = Some(std::mem::replace(text, translated));
+        index += 1;
+    }
+
+    let _ = app.emit(
+        "subtitle-translation-progress",
+        SubtitleProgress {
+            id: request.request_id,
+            index: total,
+            total,
+        },
+    );
+
+    Ok(SubtitleTranslateResponse {
+        translated_text: join_segments(&segments, &request.text),
+        cues: total,
+    })
+}
+
+/// 翻訳結果を保存する。元のファイルと同じ場所に `名前.<翻訳先>.<拡張子>` を提案し、保存先をダイアログで選ばせる
+/// キャンセルされた場合は None
+#[tauri::command]
+pub async fn save_translated_file(
+    app: tauri::AppHandle,
+    source_path: String,
+    contents: String,
+    target_lang: String,
+) -> Result<Option<String>, String> {
+    let source_path = PathBuf::from(source_path);
+    let stem = source_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "translation".to_string());
+    let extension = source_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_else(|| "txt".to_string());
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let mut dialog = app
+        .dialog()
+        .file()
+        .add_filter(extension.to_uppercase(), &[extension.as_str()])
+        .set_file_name(format!("{}.{}.{}", stem, target_lang, extension));
+    if let Some(dir) = source_path.parent() {
+        dialog = dialog.set_directory(dir);
+    }
+    dialog.save_file(move |path| {
+        let _ = tx.send(path);
+    });
+
+    let Some(path) = rx.await.map_err(|e| format!("Failed to open file dialog: {}", e))? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| format!("Invalid file path: {}", e))?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn write_temp(bytes: &[u8]) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), bytes).unwrap();
+        file
+    }
+
+    #[test]
+    fn strips_utf8_bom() {
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend_from_slice("こんにちは".as_bytes());
+        assert_eq!(decode_text(&bytes, Path::new("a.txt")).unwrap(), "こんにちは");
+    }
+
+    #[test]
+    fn decodes_utf16le_with_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16le("Hello, 世界"));
+        assert_eq!(decode_text(&bytes, Path::new("a.txt")).unwrap(), "Hello, 世界");
+    }
+
+    #[test]
+    fn reads_utf8_without_bom() {
+        assert_eq!(decode_text("plain".as_bytes(), Path::new("a.txt")).unwrap(), "plain");
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        let error = decode_text(&[0xFF, 0x00, 0xC3], Path::new("a.bin")).unwrap_err();
+        assert!(error.contains("invalid UTF-8"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_utf16_without_bom_as_binary() {
+        // ASCIIだけのUTF-16LEはUTF-8としては読めるがNUL文字だらけになる
+        let file = write_temp(&utf16le("Hello"));
+        let error = read_text_file(file.path()).unwrap_err();
+        assert!(error.contains("contains binary data"), "{}", error);
+    }
+
+    #[test]
+    fn reads_utf16le_file_with_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16le("翻訳"));
+        let file = write_temp(&bytes);
+        assert_eq!(read_text_file(file.path()).unwrap(), "翻訳");
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::error::TranslateError;
+use crate::{provider, storage};
+
+/// 履歴の埋め込みベクトルを保存するファイル（履歴本体はフロントエンドのlocalStorageにある）
+const EMBEDDINGS_FILE: &str = "history_embeddings.json";
+
+const DEFAULT_TOP_K: usize = 10;
+
+/// 埋め込みファイルの読み書きを直列化するためのロック（埋め込みの計算中も保持する）
+pub struct EmbeddingsLock(pub tokio::sync::Mutex<()>);
+
+/// 履歴1件分の埋め込み。原文・訳文やモデルが変わったら作り直す
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct StoredEmbedding {
+    model: String,
+    text_hash: u64,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmbedRequest {
+    pub text: String,
+    pub endpoint: String,
+    pub model: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub headers: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub source_text: String,
+    pub translated_text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SemanticSearchRequest {
+    pub query: String,
+    /// 検索対象の履歴（フロントエンドが保持している全件）
+    pub entries: Vec<HistoryEntry>,
+    pub endpoint: String,
+    /// 埋め込みモデル（"nomic-embed-text"など）。未設定なら部分一致で検索する
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    #[serde(default)]
+    pub top_k: Option<usize>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub headers: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HistoryMatch {
+    pub id: String,
+    /// コサイン類似度（部分一致で検索した場合は1.0）
+    pub score: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyEmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+fn entry_text(entry: &HistoryEntry) -> String {
+    format!("{}\n{}", entry.source_text, entry.translated_text)
+}
+
+fn text_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Ollamaで複数のテキストをまとめて埋め込む（`/api/embed`）
+/// `/api/embed` の無い古いOllamaでは1件ずつ `/api/embeddings` を呼ぶ
+async fn embed(
+    endpoint: &str,
+    model: &str,
+    api_key: Option<&str>,
+    headers: Option<&HashMap<String, String>>,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, TranslateError> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(120))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let headers = provider::parse_headers(headers)?;
+    let post = |path: &str| {
+        provider::with_auth(client.post(format!("{}{}", endpoint, path)), api_key).headers(headers.clone())
+    };
+
+    let response = post("/api/embed")
+        .json(&serde_json::json!({ "model": model, "input": inputs }))
+        .send()
+        .await
+        .map_err(|e| TranslateError::from_reqwest("Failed to send request", e))?;
+    if response.status() != reqwest::StatusCode::NOT_FOUND {
+        let parsed: EmbedResponse = provider::check_status(response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| TranslateError::from_reqwest("Failed to parse response", e))?;
+        if parsed.embeddings.len() != inputs.len() {
+            return Err(TranslateError::ParseError {
+                message: format!(
+                    "Expected {} embeddings but received {}",
+                    inputs.len(),
+                    parsed.embeddings.len()
+                ),
+            });
+        }
+        return Ok(parsed.embeddings);
+    }
+
+    let mut embeddings = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let response = post("/api/embeddings")
+            .json(&serde_json::json!({ "model": model, "prompt": input }))
+            .send()
+            .await
+            .map_err(|e| TranslateError::from_reqwest("Failed to send request", e))?;
+        let parsed: LegacyEmbeddingResponse = provider::check_status(response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| TranslateError::from_reqwest("Failed to parse response", e))?;
+        embeddings.push(parsed.embedding);
+    }
+    Ok(embeddings)
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// 大文字小文字を区別せず、原文か訳文に検索語を含む履歴を新しい順（渡された順）に返す
+fn substring_search(query: &str, entries: &[HistoryEntry], top_k: usize) -> Vec<HistoryMatch> {
+    let query = query.to_lowercase();
+    entries
+        .iter()
+        .filter(|entry| {
+            entry.source_text.to_lowercase().contains(&query) || entry.translated_text.to_lowercase().contains(&query)
+        })
+        .take(top_k)
+        .map(|entry| HistoryMatch {
+            id: entry.id.clone(),
+            score: 1.0,
+        })
+        .collect()
+}
+
+/// テキストの埋め込みベクトルを返す
+#[tauri::command]
+pub async fn embed_text(request: EmbedRequest) -> Result<Vec<f32>, TranslateError> {
+    let mut embeddings = embed(
+        &request.endpoint,
+        &request.model,
+        request.api_key.as_deref(),
+        request.headers.as_ref(),
+        &[request.text],
+    )
+    .await?;
+    Ok(embeddings.remove(0))
+}
+
+/// 履歴を意味の近さで検索し、類似度の高い順に最大 `top_k` 件返す
+/// 埋め込みは履歴のIDごとに保存して使い回し、未計算・内容が変わった履歴だけをまとめて埋め込む
+/// 埋め込みモデルが未設定の場合は部分一致で検索する
+#[tauri::command]
+pub async fn search_history_semantic(
+    app: tauri::AppHandle,
+    request: SemanticSearchRequest,
+) -> Result<Vec<HistoryMatch>, TranslateError> {
+    let top_k = request.top_k.unwrap_or(DEFAULT_TOP_K);
+    let query = request.query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(model) = request.embedding_model.as_deref().filter(|m| !m.trim().is_empty()) else {
+        return Ok(substring_search(query, &request.entries, top_k));
+    };
+
+    let state = app.state::<EmbeddingsLock>();
+    let _guard = state.0.lock().await;
+    let path = storage::data_file(&app, EMBEDDINGS_FILE)?;
+    let mut stored: HashMap<String, StoredEmbedding> = storage::read_json(&path)?;
+
+    // 埋め込みが無いか古い履歴と、検索語をまとめて埋め込む
+    let stale: Vec<&HistoryEntry> = request
+        .entries
+        .iter()
+        .filter(|entry| {
+            stored.get(&entry.id).is_none_or(|existing| {
+                existing.model != model || existing.text_hash != text_hash(&entry_text(entry))
+            })
+        })
+        .collect();
+    let mut inputs: Vec<String> = stale.iter().map(|entry| entry_text(entry)).collect();
+    inputs.push(query.to_string());
+    let mut embeddings = embed(
+        &request.endpoint,
+        model,
+        request.api_key.as_deref(),
+        request.headers.as_ref(),
+        &inputs,
+    )
+    .await?;
+    let query_embedding = embeddings.pop().unwrap_or_default();
+    for ((entry, input), embedding) in stale.iter().zip(&inputs).zip(embeddings) {
+        stored.insert(
+            entry.id.clone(),
+            StoredEmbedding {
+                model: model.to_string(),
+                text_hash: text_hash(input),
+                embedding,
+            },
+        );
+    }
+
+    // 削除された履歴の埋め込みは捨てる
+    stored.retain(|id, _| request.entries.iter().any(|entry| &entry.id == id));
+    storage::write_json(&path, &stored)?;
+
+    let mut matches: Vec<HistoryMatch> = request
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let embedding = &stored.get(&entry.id)?.embedding;
+            Some(HistoryMatch {
+                id: entry.id.clone(),
+                score: cosine_similarity(&query_embedding, embedding),
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches.truncate(top_k);
+    Ok(matches)
+}
@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::{Emitter, Manager};
+
+use crate::{languages, notices, popup, preferences};
+
+// カーソル位置を確認する間隔
+const HOVER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// カーソルがこの時間止まっていたら、その位置の選択範囲を翻訳する
+const HOVER_DWELL: Duration = Duration::from_millis(600);
+// 手ぶれとみなして止まっている扱いにする移動量（物理ピクセル）
+const HOVER_MOVE_TOLERANCE: f64 = 4.0;
+
+/// ホバー翻訳の状態。ショートカットを押している間だけカーソルを監視する
+#[derive(Default)]
+pub struct HoverState {
+    /// ショートカットを押している間 true
+    held: AtomicBool,
+    /// 監視スレッドが動いている間 true（押しっぱなしのキーリピートで複数起動しないように）
+    running: AtomicBool,
+    /// 最後に翻訳したテキスト（同じ場所で止まり直しても訳し直さない）
+    last_text: Mutex<Option<String>>,
+}
+
+/// ショートカットが押されたらカーソルの監視を始める
+pub fn start(app: &tauri::AppHandle) {
+    let state = app.state::<HoverState>();
+    state.held.store(true, Ordering::SeqCst);
+    if state.running.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    if let Ok(mut last_text) = state.last_text.lock() {
+        *last_text = None;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        watch_cursor(&app);
+        app.state::<HoverState>().running.store(false, Ordering::SeqCst);
+    });
+}
+
+/// ショートカットが離されたら監視をやめ、ポップアップを隠す
+pub fn stop(app: &tauri::AppHandle) {
+    app.state::<HoverState>().held.store(false, Ordering::SeqCst);
+    if let Some(window) = app.get_webview_window(popup::POPUP_LABEL) {
+        let _ = window.hide();
+    }
+}
+
+/// ショートカットを押している間カーソル位置を監視し、止まるたびに1回だけ選択範囲のコピーと翻訳を行う
+fn watch_cursor(app: &tauri::AppHandle) {
+    let mut anchor = None;
+    let mut anchored_at = Instant::now();
+    let mut triggered = false;
+
+    while app.state::<HoverState>().held.load(Ordering::SeqCst) {
+        std::thread::sleep(HOVER_POLL_INTERVAL);
+        let Ok(position) = app.cursor_position() else {
+            continue;
+        };
+
+        let moved = anchor.is_none_or(|(x, y): (f64, f64)| {
+            (position.x - x).abs() > HOVER_MOVE_TOLERANCE || (position.y - y).abs() > HOVER_MOVE_TOLERANCE
+        });
+        if moved {
+            anchor = Some((position.x, position.y));
+            anchored_at = Instant::now();
+            triggered = false;
+            continue;
+        }
+
+        if !triggered && anchored_at.elapsed() >= HOVER_DWELL {
+            triggered = true;
+            crate::copy_selection(app, |app, text| {
+                if let Some(text) = text.filter(|text| !text.trim().is_empty()) {
+                    translate_hovered(app, text);
+                }
+            });
+        }
+    }
+}
+
+/// 前回と違うテキストなら、カーソル付近にフォーカスを奪わずにポップアップを出して翻訳結果を表示する
+fn translate_hovered(app: &tauri::AppHandle, text: String) {
+    let state = app.state::<HoverState>();
+    {
+        let Ok(mut last_text) = state.last_text.lock() else {
+            return;
+        };
+        if last_text.as_deref() == Some(text.as_str()) {
+            return;
+        }
+        *last_text = Some(text.clone());
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        // コピー先がポップアップにならないよう、ポップアップにはフォーカスを移さない
+        if let Err(e) = popup::show_at_cursor(&app, false) {
+            notices::report(format!("Failed to show hover popup: {}", e));
+            return;
+        }
+
+        let target_lang = preferences::get(&app)
+            .ok()
+            .and_then(|prefs| prefs.inline_target_lang)
+            .unwrap_or_else(|| crate::DEFAULT_INLINE_TARGET_LANG.to_string());
+        let result = match crate::request_with_active_profile(&app, text, languages::AUTO.to_string(), target_lang).await {
+            Ok(request) => crate::run_translation(app.clone(), request, false).await,
+            Err(e) => Err(e.into()),
+        };
+        // 翻訳中にショートカットが離されていたら表示しない
+        if !app.state::<HoverState>().held.load(Ordering::SeqCst) {
+            return;
+        }
+        let text = match result {
+            Ok(response) => response.translated_text,
+            Err(e) => e.to_string(),
+        };
+        let _ = app.emit_to(popup::POPUP_LABEL, "result-popup-text", text);
+    });
+}
@@ -0,0 +1,307 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::error::TranslateError;
+use crate::{languages, notices, preferences};
+
+/// ポート未設定時の待ち受けポート
+pub const DEFAULT_PORT: u16 = 18080;
+
+// ヘッダーと本文の上限（ローカルツールからの翻訳依頼には十分な大きさ）
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// 起動中のサーバー（停止時はタスクを中断してリスナーを閉じる）
+struct RunningServer {
+    port: u16,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+pub struct HttpServerState(Mutex<Option<RunningServer>>);
+
+impl HttpServerState {
+    pub fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+}
+
+/// `POST /translate` の本文
+#[derive(Debug, Deserialize)]
+struct HttpTranslateRequest {
+    text: String,
+    #[serde(default = "auto_lang")]
+    source_lang: String,
+    target_lang: String,
+}
+
+fn auto_lang() -> String {
+    languages::AUTO.to_string()
+}
+
+#[derive(Debug, Serialize)]
+struct HttpTranslateResponse {
+    translated_text: String,
+    detected_lang: Option<String>,
+    truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct HttpErrorResponse {
+    error: TranslateError,
+}
+
+/// 127.0.0.1 の `port` で待ち受けを始める（起動中のサーバーは先に止める）
+async fn start(app: &tauri::AppHandle, port: u16) -> Result<(), String> {
+    stop(app)?;
+
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Failed to bind 127.0.0.1:{}: {}", port, e))?;
+
+    let handle = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    notices::report(format!("Failed to accept HTTP connection: {}", e));
+                    continue;
+                }
+            };
+            let app = handle.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = handle_connection(&app, stream).await {
+                    notices::report(format!("HTTP server error: {}", e));
+                }
+            });
+        }
+    });
+
+    let state = app.state::<HttpServerState>();
+    let mut guard = state
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to lock HTTP server state: {}", e))?;
+    *guard = Some(RunningServer { port, task });
+    Ok(())
+}
+
+/// 起動中のサーバーを止める（起動していなければ何もしない）
+pub fn stop(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<HttpServerState>();
+    let mut guard = state
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to lock HTTP server state: {}", e))?;
+    if let Some(server) = guard.take() {
+        server.task.abort();
+    }
+    Ok(())
+}
+
+/// 起動時に、保存済みの設定で有効ならサーバーを立ち上げる
+pub fn start_if_enabled(app: &tauri::AppHandle) {
+    let Ok(prefs) = preferences::get(app) else {
+        return;
+    };
+    if !prefs.http_server_enabled {
+        return;
+    }
+    let port = prefs.http_server_port.unwrap_or(DEFAULT_PORT);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = start(&app, port).await {
+            notices::report(format!("Failed to start HTTP server: {}", e));
+        }
+    });
+}
+
+/// ローカルHTTPサーバーの起動・停止を切り替え、設定に保存する。`port` 未指定時は保存済みの値か既定値
+#[tauri::command]
+pub async fn set_http_server_enabled(
+    app: tauri::AppHandle,
+    enabled: bool,
+    port: Option<u16>,
+) -> Result<(), String> {
+    let port = port
+        .or(preferences::get(&app)?.http_server_port)
+        .unwrap_or(DEFAULT_PORT);
+
+    if enabled {
+        let running_port = app
+            .state::<HttpServerState>()
+            .0
+            .lock()
+            .map_err(|e| format!("Failed to lock HTTP server state: {}", e))?
+            .as_ref()
+            .map(|server| server.port);
+        if running_port != Some(port) {
+            start(&app, port).await?;
+        }
+    } else {
+        stop(&app)?;
+    }
+
+    preferences::update(&app, |prefs| {
+        prefs.http_server_enabled = enabled;
+        prefs.http_server_port = Some(port);
+    })
+}
+
+/// 1接続につき1リクエストを処理して閉じる（keep-aliveには対応しない）
+async fn handle_connection(app: &tauri::AppHandle, mut stream: TcpStream) -> Result<(), String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    // ヘッダーを最後まで読む
+    let (method, path, content_type, content_length, header_len) = loop {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read request: {}", e))?;
+        if read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut request = httparse::Request::new(&mut headers);
+        match request.parse(&buffer) {
+            Ok(httparse::Status::Complete(header_len)) => {
+                let header = |name: &str| {
+                    request
+                        .headers
+                        .iter()
+                        .find(|h| h.name.eq_ignore_ascii_case(name))
+                        .and_then(|h| std::str::from_utf8(h.value).ok())
+                        .map(str::trim)
+                };
+                let content_length = header("Content-Length").and_then(|v| v.parse::<usize>().ok());
+                let content_type = header("Content-Type").unwrap_or_default().to_string();
+                break (
+                    request.method.unwrap_or_default().to_string(),
+                    request.path.unwrap_or_default().to_string(),
+                    content_type,
+                    content_length,
+                    header_len,
+                );
+            }
+            Ok(httparse::Status::Partial) if buffer.len() < MAX_HEADER_BYTES => continue,
+            Ok(httparse::Status::Partial) => {
+                return respond_error(&mut stream, 431, "Request header too large").await;
+            }
+            Err(e) => return respond_error(&mut stream, 400, &format!("Invalid request: {}", e)).await,
+        }
+    };
+
+    if path != "/translate" {
+        return respond_error(&mut stream, 404, "Not found").await;
+    }
+    if method != "POST" {
+        return respond_error(&mut stream, 405, "Method not allowed").await;
+    }
+    // application/json 以外を拒否し、ブラウザ上の任意のページから単純リクエストで呼ばれないようにする
+    if !content_type.to_ascii_lowercase().starts_with("application/json") {
+        return respond_error(&mut stream, 415, "Content-Type must be application/json").await;
+    }
+    let Some(content_length) = content_length else {
+        return respond_error(&mut stream, 411, "Content-Length is required").await;
+    };
+    if content_length > MAX_BODY_BYTES {
+        return respond_error(&mut stream, 413, "Request body too large").await;
+    }
+
+    // 本文を読む
+    let mut body = buffer.split_off(header_len);
+    while body.len() < content_length {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read request body: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(content_length);
+
+    let request: HttpTranslateRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => return respond_error(&mut stream, 400, &format!("Invalid JSON: {}", e)).await,
+    };
+    if request.text.trim().is_empty() {
+        return respond_error(&mut stream, 400, "text is required").await;
+    }
+
+    match translate(app, request).await {
+        Ok(response) => respond_json(&mut stream, 200, &response).await,
+        Err(error) => {
+            let status = match error {
+                TranslateError::Other { .. } => 400,
+                TranslateError::Timeout { .. } => 504,
+                _ => 502,
+            };
+            respond_json(&mut stream, status, &HttpErrorResponse { error }).await
+        }
+    }
+}
+
+/// アクティブなプロファイル（無ければCLIと同じ既定値）でアプリ内の翻訳処理を呼ぶ
+async fn translate(
+    app: &tauri::AppHandle,
+    request: HttpTranslateRequest,
+) -> Result<HttpTranslateResponse, TranslateError> {
+    let translate_request =
+        crate::request_with_active_profile(app, request.text, request.source_lang, request.target_lang).await?;
+
+    let response = crate::run_translation(app.clone(), translate_request, false).await?;
+    Ok(HttpTranslateResponse {
+        translated_text: response.translated_text,
+        detected_lang: response.detected_lang,
+        truncated: response.truncated,
+    })
+}
+
+async fn respond_error(stream: &mut TcpStream, status: u16, message: &str) -> Result<(), String> {
+    let error = TranslateError::Other {
+        message: message.to_string(),
+    };
+    respond_json(stream, status, &HttpErrorResponse { error }).await
+}
+
+async fn respond_json<T: Serialize>(stream: &mut TcpStream, status: u16, body: &T) -> Result<(), String> {
+    let body = serde_json::to_vec(body).map_err(|e| format!("Failed to serialize response: {}", e))?;
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        431 => "Request Header Fields Too Large",
+        502 => "Bad Gateway",
+        504 => "Gateway Timeout",
+        _ => "Error",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        body.len()
+    );
+    stream
+        .write_all(head.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write response: {}", e))?;
+    stream
+        .write_all(&body)
+        .await
+        .map_err(|e| format!("Failed to write response: {}", e))?;
+    let _ = stream.shutdown().await;
+    Ok(())
+}
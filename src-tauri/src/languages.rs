@@ -0,0 +1,110 @@
+use serde::Serialize;
+
+/// 対応言語の定義（UIのドロップダウンとプロンプト生成で共有する）
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct Language {
+    pub code: &'static str,
+    pub english_name: &'static str,
+    pub native_name: &'static str,
+}
+
+/// 自動検出を表す特別なエントリ
+pub const AUTO: &str = "auto";
+
+const LANGUAGES: &[Language] = &[
+    Language { code: AUTO, english_name: "Auto-detect", native_name: "自動検出" },
+    Language { code: "ja", english_name: "Japanese", native_name: "日本語" },
+    Language { code: "en", english_name: "English", native_name: "English" },
+    Language { code: "zh", english_name: "Chinese", native_name: "中文" },
+    Language { code: "ko", english_name: "Korean", native_name: "한국어" },
+    Language { code: "fr", english_name: "French", native_name: "Français" },
+    Language { code: "de", english_name: "German", native_name: "Deutsch" },
+    Language { code: "es", english_name: "Spanish", native_name: "Español" },
+];
+
+/// 言語コードまたは英語名から言語を探す（以前の設定は英語名で保存されているため両方受け付ける）
+pub fn find(code_or_name: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|lang| {
+        lang.code.eq_ignore_ascii_case(code_or_name)
+            || lang.english_name.eq_ignore_ascii_case(code_or_name)
+    })
+}
+
+/// プロンプト用に英語名へ変換する。未知の値はそのまま返す
+pub fn english_name(code_or_name: &str) -> &str {
+    find(code_or_name)
+        .map(|lang| lang.english_name)
+        .unwrap_or(code_or_name)
+}
+
+/// 文字種と頻出語から原文の言語を簡易的に推定する（モデルを呼ばずに済むよう軽い判定に留める）
+/// かな→日本語、ハングル→韓国語、かなを含まない漢字→中国語、ラテン文字は頻出語とアクセント記号で判定する
+pub fn detect(text: &str) -> Option<&'static str> {
+    let (mut kana, mut hangul, mut han, mut latin) = (0usize, 0usize, 0usize, 0usize);
+    for c in text.chars() {
+        match c {
+            '\u{3040}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => kana += 1,
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' => hangul += 1,
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => han += 1,
+            c if c.is_alphabetic() && (c.is_ascii() || ('\u{00C0}'..='\u{024F}').contains(&c)) => latin += 1,
+            _ => {}
+        }
+    }
+
+    if kana > 0 {
+        return Some("ja");
+    }
+    if hangul > 0 && hangul >= han {
+        return Some("ko");
+    }
+    if han > 0 && han >= latin {
+        return Some("zh");
+    }
+    if latin == 0 {
+        return None;
+    }
+    Some(detect_latin(text))
+}
+
+// ラテン文字の言語ごとの頻出語（短い文でも1〜2語は含まれることが多いもの）
+const LATIN_STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "is", "are", "of", "to", "in", "that", "it", "with", "for", "this", "you"]),
+    ("fr", &["le", "la", "les", "et", "est", "des", "une", "un", "du", "que", "pour", "dans", "avec", "je", "vous"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "ein", "eine", "mit", "ich", "sie", "zu", "den", "auf"]),
+    ("es", &["el", "los", "las", "y", "es", "una", "por", "con", "que", "para", "del", "está", "yo", "pero"]),
+];
+
+fn detect_latin(text: &str) -> &'static str {
+    let lower = text.to_lowercase();
+    let mut scores: Vec<(&'static str, usize)> = LATIN_STOPWORDS
+        .iter()
+        .map(|(code, words)| {
+            let hits = lower
+                .split(|c: char| !c.is_alphabetic())
+                .filter(|word| words.contains(word))
+                .count();
+            (*code, hits)
+        })
+        .collect();
+
+    // 固有の文字があれば加点する
+    for (code, chars) in [("de", "äöüß"), ("fr", "àâçèêëîïôœùû"), ("es", "ñ¿¡áíóú")] {
+        let hits = lower.chars().filter(|c| chars.contains(*c)).count();
+        if let Some(score) = scores.iter_mut().find(|(c, _)| *c == code) {
+            score.1 += hits;
+        }
+    }
+
+    // 同点なら英語を優先する（先頭に置いているため max_by_key の逆順で最初の最大値を取る）
+    scores
+        .iter()
+        .rev()
+        .max_by_key(|(_, hits)| *hits)
+        .map(|(code, _)| *code)
+        .unwrap_or("en")
+}
+
+#[tauri::command]
+pub fn supported_languages() -> Vec<Language> {
+    LANGUAGES.to_vec()
+}
@@ -0,0 +1,97 @@
+/// 改行区切りJSON（Ollamaのストリーミング応答）を行単位に組み立てるデコーダー
+///
+/// - 1つのJSONオブジェクトがネットワークのチャンク境界で分割されて届くことがあるため、改行まではバイト列のまま保持する
+/// - UTF-8の複数バイト文字が分割された場合も、行がそろってから文字列にするので壊れない
+/// - 空行は返さない
+/// - 1行が `MAX_LINE_BYTES` を超えたら、それ以上ため込まずにエラーにする
+#[derive(Debug, Default)]
+pub struct LineDecoder {
+    // まだ改行が届いていない行の断片
+    partial: Vec<u8>,
+    // `partial` のうち改行が無いと確認済みのバイト数
+    scanned: usize,
+}
+
+/// 1行の最大バイト数
+pub const MAX_LINE_BYTES: usize = crate::sse::MAX_LINE_BYTES;
+
+impl LineDecoder {
+    /// 受信したバイト列を追加し、改行まで届いた行を返す
+    /// 改行の無いまま `MAX_LINE_BYTES` を超えた行があればエラーを返す
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<String>, String> {
+        self.partial.extend_from_slice(bytes);
+
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while let Some(pos) = self.partial[start + self.scanned..].iter().position(|b| *b == b'\n') {
+            let end = start + self.scanned + pos;
+            self.scanned = 0;
+            check_line_length(end - start)?;
+            let line = String::from_utf8_lossy(&self.partial[start..end]);
+            let line = line.trim();
+            if !line.is_empty() {
+                lines.push(line.to_string());
+            }
+            start = end + 1;
+        }
+        // 処理した行はまとめて取り除く
+        self.partial.drain(..start);
+        self.scanned = self.partial.len();
+        check_line_length(self.partial.len())?;
+        Ok(lines)
+    }
+
+    /// ストリーム終了時に、改行で終わっていない最後の行を返す
+    pub fn finish(&mut self) -> Option<String> {
+        self.scanned = 0;
+        let line = String::from_utf8_lossy(&std::mem::take(&mut self.partial))
+            .trim()
+            .to_string();
+        (!line.is_empty()).then_some(line)
+    }
+}
+
+fn check_line_length(len: usize) -> Result<(), String> {
+    if len > MAX_LINE_BYTES {
+        return Err(format!(
+            "NDJSON line exceeds the maximum length of {} bytes",
+            MAX_LINE_BYTES
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassembles_json_object_split_across_chunks() {
+        let mut decoder = LineDecoder::default();
+        let line = "{\"response\":\"こんにちは\",\"done\":false}\n".as_bytes();
+        // 複数バイト文字の途中で分割する
+        let (first, second) = line.split_at(16);
+        assert!(decoder.push(first).unwrap().is_empty());
+        let lines = decoder.push(second).unwrap();
+        assert_eq!(lines, vec!["{\"response\":\"こんにちは\",\"done\":false}"]);
+        let parsed: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(parsed["response"], "こんにちは");
+    }
+
+    #[test]
+    fn returns_several_lines_and_skips_blank_ones() {
+        let mut decoder = LineDecoder::default();
+        let lines = decoder.push(b"{\"a\":1}\n\n{\"b\":2}\r\n{\"c\"").unwrap();
+        assert_eq!(lines, vec!["{\"a\":1}", "{\"b\":2}"]);
+        assert_eq!(decoder.push(b":3}").unwrap(), Vec::<String>::new());
+        assert_eq!(decoder.finish().as_deref(), Some("{\"c\":3}"));
+    }
+
+    #[test]
+    fn rejects_line_over_limit() {
+        let mut decoder = LineDecoder::default();
+        let chunk = vec![b'x'; MAX_LINE_BYTES / 2 + 1];
+        assert!(decoder.push(&chunk).is_ok());
+        assert!(decoder.push(&chunk).is_err());
+    }
+}
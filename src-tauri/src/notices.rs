@@ -0,0 +1,33 @@
+use std::sync::{Mutex, OnceLock};
+
+use tauri::Emitter;
+
+/// フロントエンドがまだ受け取っていない失敗のメッセージ
+/// 起動処理中（ウェブビューがイベントを待ち受ける前）の失敗も失わないよう、取り出されるまで残す
+static PENDING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static APP: OnceLock<tauri::AppHandle> = OnceLock::new();
+
+/// `backend-error` イベントを送れるようにする（`setup` の最初に呼ぶ）
+pub fn init(app: &tauri::AppHandle) {
+    let _ = APP.set(app.clone());
+}
+
+/// コマンドの戻り値で返せない失敗（起動時のショートカット登録・HTTPサーバー・壊れた設定ファイルの退避など）を
+/// フロントエンドに知らせる。メッセージを溜めて `backend-error` を送り、フロントエンドは `take_backend_errors` で受け取る
+pub fn report(message: String) {
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.push(message);
+    }
+    if let Some(app) = APP.get() {
+        let _ = app.emit("backend-error", ());
+    }
+}
+
+/// 溜まっている失敗のメッセージを古い順に取り出す
+#[tauri::command]
+pub fn take_backend_errors() -> Result<Vec<String>, String> {
+    let mut pending = PENDING
+        .lock()
+        .map_err(|e| format!("Failed to lock backend errors: {}", e))?;
+    Ok(std::mem::take(&mut *pending))
+}
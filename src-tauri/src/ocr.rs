@@ -0,0 +1,215 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::{TranslateRequest, TranslateResponse};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScreenRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OcrTranslateResponse {
+    /// OCRで認識された原文
+    pub source_text: String,
+    pub translation: TranslateResponse,
+}
+
+/// アプリの言語名をTesseractの言語コードに変換（自動検出時は英語+日本語）
+fn tesseract_lang(source_lang: &str) -> &'static str {
+    match crate::languages::english_name(source_lang) {
+        "Japanese" => "jpn",
+        "English" => "eng",
+        "Chinese" => "chi_sim",
+        "Korean" => "kor",
+        "French" => "fra",
+        "German" => "deu",
+        "Spanish" => "spa",
+        _ => "eng+jpn",
+    }
+}
+
+fn temp_capture_path() -> PathBuf {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    std::env::temp_dir().join(format!("translator-ocr-{}.png", stamp))
+}
+
+async fn run(command: &mut Command, name: &str) -> Result<Vec<u8>, String> {
+    let output = command
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", name, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// 画面の指定領域をPNGとして保存する（プラットフォーム別のキャプチャツールを使用）
+async fn capture_region(region: &ScreenRegion, path: &Path) -> Result<(), String> {
+    if region.width == 0 || region.height == 0 {
+        return Err("Capture region must not be empty".to_string());
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        run(
+            Command::new("screencapture").args([
+                "-x",
+                "-R",
+                &format!("{},{},{},{}", region.x, region.y, region.width, region.height),
+                &path.to_string_lossy(),
+            ]),
+            "screencapture",
+        )
+        .await?;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let script = format!(
+            r#"
+            Add-Type -AssemblyName System.Drawing
+            $bmp = New-Object System.Drawing.Bitmap({w}, {h})
+            $g = [System.Drawing.Graphics]::FromImage($bmp)
+            $g.CopyFromScreen({x}, {y}, 0, 0, $bmp.Size)
+            $bmp.Save('{path}', [System.Drawing.Imaging.ImageFormat]::Png)
+            $g.Dispose()
+            $bmp.Dispose()
+            "#,
+            x = region.x,
+            y = region.y,
+            w = region.width,
+            h = region.height,
+            path = path.to_string_lossy().replace('\'', "''"),
+        );
+        run(
+            Command::new("powershell").args(["-NoProfile", "-Command", &script]),
+            "powershell",
+        )
+        .await?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            run(
+                Command::new("grim").args([
+                    "-g",
+                    &format!("{},{} {}x{}", region.x, region.y, region.width, region.height),
+                    &path.to_string_lossy(),
+                ]),
+                "grim",
+            )
+            .await?;
+        } else {
+            // X11ではImageMagickのimportを使用
+            run(
+                Command::new("import").args([
+                    "-window",
+                    "root",
+                    "-crop",
+                    &format!("{}x{}+{}+{}", region.width, region.height, region.x, region.y),
+                    &path.to_string_lossy(),
+                ]),
+                "import",
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// システムにインストールされたTesseractで画像からテキストを抽出する
+pub async fn recognize_text(image_path: &Path, source_lang: &str) -> Result<String, String> {
+    let stdout = run(
+        Command::new("tesseract").args([
+            &*image_path.to_string_lossy(),
+            "stdout",
+            "-l",
+            tesseract_lang(source_lang),
+        ]),
+        "tesseract",
+    )
+    .await?;
+
+    Ok(String::from_utf8_lossy(&stdout).trim().to_string())
+}
+
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    writer
+        .write_image_data(rgba)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(bytes)
+}
+
+/// クリップボードの画像をPNGにして返す（画像が無い場合はNone）
+/// Linuxではメインスレッドから読むとデッドロックすることがあるため、別スレッドから呼ぶこと
+pub fn read_clipboard_png(app: &tauri::AppHandle) -> Result<Option<Vec<u8>>, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    // テキストのみ・空のクリップボードでもエラーになるため、画像が無いものとして扱う
+    let Ok(image) = app.clipboard().read_image() else {
+        return Ok(None);
+    };
+    encode_png(image.rgba(), image.width(), image.height()).map(Some)
+}
+
+/// PNG画像を一時ファイルに書き出してOCRする
+pub async fn recognize_png(png: &[u8], source_lang: &str) -> Result<String, String> {
+    let path = temp_capture_path();
+    std::fs::write(&path, png).map_err(|e| format!("Failed to write image: {}", e))?;
+    let recognized = recognize_text(&path, source_lang).await;
+    let _ = std::fs::remove_file(&path);
+    recognized
+}
+
+#[tauri::command]
+pub async fn translate_screen_region(
+    app: tauri::AppHandle,
+    region: ScreenRegion,
+    mut request: TranslateRequest,
+) -> Result<OcrTranslateResponse, String> {
+    let path = temp_capture_path();
+    let recognized = match capture_region(&region, &path).await {
+        Ok(()) => recognize_text(&path, &request.source_lang).await,
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_file(&path);
+    let source_text = recognized?;
+
+    if source_text.is_empty() {
+        return Err("No text was recognized in the selected region".to_string());
+    }
+
+    request.text = source_text.clone();
+    let translation = crate::translate(app, request).await?;
+
+    Ok(OcrTranslateResponse {
+        source_text,
+        translation,
+    })
+}
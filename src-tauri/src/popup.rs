@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tauri::{Emitter, Listener, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::preferences;
+
+pub const POPUP_LABEL: &str = "popup";
+
+const POPUP_WIDTH: f64 = 380.0;
+const POPUP_HEIGHT: f64 = 220.0;
+// カーソルと重ならないようにずらす量（物理ピクセル）
+const CURSOR_OFFSET: f64 = 16.0;
+
+/// 自動で隠すタイマーの世代。タイマーは開始時の世代を覚えておき、待ち終えた時に変わっていなければ隠す
+/// 世代を進めると待機中のタイマーは全て取り消される
+#[derive(Default)]
+pub struct PopupAutoHide(AtomicU64);
+
+/// `translation-done` を受けたら自動で隠すタイマーを始める
+pub fn init(app: &tauri::AppHandle) {
+    let handle = app.clone();
+    app.listen("translation-done", move |_| start_auto_hide(&handle));
+}
+
+/// 表示中のポップアップを `popup_auto_hide_ms` 後に隠すタイマーを始める（前のタイマーは取り消す）。未設定なら何もしない
+fn start_auto_hide(app: &tauri::AppHandle) {
+    let Some(delay) = preferences::get(app).ok().and_then(|prefs| prefs.popup_auto_hide_ms) else {
+        return;
+    };
+    let Some(window) = app.get_webview_window(POPUP_LABEL) else {
+        return;
+    };
+    if !window.is_visible().unwrap_or(false) {
+        return;
+    }
+
+    let app = app.clone();
+    let generation = app.state::<PopupAutoHide>().0.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+        if app.state::<PopupAutoHide>().0.load(Ordering::SeqCst) == generation {
+            let _ = window.hide();
+        }
+    });
+}
+
+/// 待機中の自動で隠すタイマーを取り消す（ポップアップへのフォーカス・ホバー、新しい翻訳の表示時）
+pub fn cancel_auto_hide(app: &tauri::AppHandle) {
+    app.state::<PopupAutoHide>().0.fetch_add(1, Ordering::SeqCst);
+}
+
+fn get_or_create_popup(app: &tauri::AppHandle) -> Result<WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(POPUP_LABEL) {
+        return Ok(window);
+    }
+
+    // フロントエンドは URL のハッシュでポップアップ表示に切り替える
+    WebviewWindowBuilder::new(app, POPUP_LABEL, WebviewUrl::App("index.html#popup".into()))
+        .title("Translation")
+        .inner_size(POPUP_WIDTH, POPUP_HEIGHT)
+        .decorations(false)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("Failed to create popup window: {}", e))
+}
+
+/// カーソル位置の近くに置く。画面端ではみ出す場合はカーソルの反対側に回り込ませる
+fn position_near_cursor(app: &tauri::AppHandle, window: &WebviewWindow) -> Result<(), String> {
+    let cursor = app
+        .cursor_position()
+        .map_err(|e| format!("Failed to get cursor position: {}", e))?;
+    let size = window
+        .outer_size()
+        .map_err(|e| format!("Failed to get popup size: {}", e))?;
+
+    let mut x = cursor.x + CURSOR_OFFSET;
+    let mut y = cursor.y + CURSOR_OFFSET;
+
+    if let Ok(Some(monitor)) = app.monitor_from_point(cursor.x, cursor.y) {
+        let origin = monitor.position();
+        let bounds = monitor.size();
+        let right = origin.x as f64 + bounds.width as f64;
+        let bottom = origin.y as f64 + bounds.height as f64;
+        if x + size.width as f64 > right {
+            x = cursor.x - CURSOR_OFFSET - size.width as f64;
+        }
+        if y + size.height as f64 > bottom {
+            y = cursor.y - CURSOR_OFFSET - size.height as f64;
+        }
+        x = x.max(origin.x as f64);
+        y = y.max(origin.y as f64);
+    }
+
+    window
+        .set_position(PhysicalPosition::new(x.round() as i32, y.round() as i32))
+        .map_err(|e| format!("Failed to position popup: {}", e))
+}
+
+/// ポップアップをカーソル付近に表示し、前回の内容をクリアさせる。`focus` が false ならフォーカスを移さない
+pub fn show_at_cursor(app: &tauri::AppHandle, focus: bool) -> Result<(), String> {
+    cancel_auto_hide(app);
+    let window = get_or_create_popup(app)?;
+    position_near_cursor(app, &window)?;
+
+    let _ = app.emit_to(POPUP_LABEL, "result-popup-reset", ());
+    window
+        .show()
+        .map_err(|e| format!("Failed to show popup: {}", e))?;
+    if focus {
+        let _ = window.set_focus();
+    }
+    Ok(())
+}
+
+/// 翻訳結果のポップアップをカーソル付近に表示し、前回の内容をクリアさせる
+#[tauri::command]
+pub async fn show_result_popup(app: tauri::AppHandle) -> Result<(), String> {
+    show_at_cursor(&app, true)
+}
+
+#[tauri::command]
+pub async fn hide_result_popup(app: tauri::AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(POPUP_LABEL) {
+        window
+            .hide()
+            .map_err(|e| format!("Failed to hide popup: {}", e))?;
+    }
+    Ok(())
+}
+
+/// ポップアップにマウスが乗った時にフロントエンドから呼び、自動で隠すのを取り消す
+#[tauri::command]
+pub async fn keep_result_popup(app: tauri::AppHandle) -> Result<(), String> {
+    cancel_auto_hide(&app);
+    Ok(())
+}
+
+/// 翻訳が終わってから結果ポップアップを自動で隠すまでの時間（ミリ秒）を設定する。None・0なら自動では隠さない
+#[tauri::command]
+pub async fn set_popup_auto_hide(app: tauri::AppHandle, auto_hide_ms: Option<u64>) -> Result<(), String> {
+    preferences::update(&app, |prefs| prefs.popup_auto_hide_ms = auto_hide_ms.filter(|&ms| ms > 0))
+}
+
+#[tauri::command]
+pub async fn get_popup_auto_hide(app: tauri::AppHandle) -> Result<Option<u64>, String> {
+    Ok(preferences::get(&app)?.popup_auto_hide_ms)
+}
@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+
+use crate::languages;
+
+/// 文の間に空白を入れない言語（英語名で判定）
+fn is_cjk_language(lang: &str) -> bool {
+    matches!(languages::english_name(lang), "Japanese" | "Chinese" | "Korean")
+}
+
+/// 改行は残したまま、連続する空白を1つにまとめる
+fn collapse_spaces(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut previous_space = false;
+    for c in text.chars() {
+        let is_space = c == ' ' || c == '\t';
+        if !(is_space && previous_space) {
+            result.push(if is_space { ' ' } else { c });
+        }
+        previous_space = is_space;
+    }
+    result
+}
+
+/// 括弧の内側の空白を取り除く（"( Tokyo )" → "(Tokyo)"）
+fn trim_inside_parens(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ' ' {
+            // 閉じ括弧の直前の空白は捨てる
+            if matches!(chars.peek(), Some(')' | '）')) {
+                continue;
+            }
+            // 開き括弧の直後の空白は捨てる
+            if matches!(result.chars().last(), Some('(' | '（')) {
+                continue;
+            }
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// 翻訳先の言語に合わせて空白を整える
+/// - ラテン文字の言語: 全角スペース・全角括弧を半角にし、"Tokyo(Toukyou)" のような括弧の前に空白を入れる
+/// - CJKの言語: 括弧の内側の余分な空白を取り除く
+pub fn normalize_spacing(text: &str, target_lang: &str) -> String {
+    if is_cjk_language(target_lang) {
+        return collapse_spaces(&trim_inside_parens(text));
+    }
+
+    let text: String = text
+        .chars()
+        .map(|c| match c {
+            '\u{3000}' => ' ',
+            '（' => '(',
+            '）' => ')',
+            _ => c,
+        })
+        .collect();
+
+    let mut result = String::with_capacity(text.len());
+    for c in trim_inside_parens(&collapse_spaces(&text)).chars() {
+        if c == '(' && result.chars().last().is_some_and(char::is_alphanumeric) {
+            result.push(' ');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// JSONモードで返された`{"translation": "...", "detected_language": "..."}`を読む
+/// 前後に説明文やコードブロックが付いていても最初の`{`から最後の`}`までを試す。解釈できなければNone
+pub fn parse_json_translation(text: &str) -> Option<(String, Option<String>)> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    let value: serde_json::Value = serde_json::from_str(text.get(start..=end)?).ok()?;
+    let translation = value.get("translation")?.as_str()?.to_string();
+    // 言語名で返された場合も対応言語なら言語コードに揃える
+    let detected = value
+        .get("detected_language")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|lang| !lang.is_empty())
+        .map(|lang| {
+            languages::find(lang)
+                .map(|found| found.code.to_string())
+                .unwrap_or_else(|| lang.to_string())
+        });
+    Some((translation, detected))
+}
+
+/// `strip_preamble` で取り除く前置きの既定の一覧（大文字小文字は区別しない）
+pub const DEFAULT_PREAMBLE_PHRASES: [&str; 14] = [
+    "Sure, here is the translation",
+    "Sure, here's the translation",
+    "Sure! Here is the translation",
+    "Here is the translation",
+    "Here's the translation",
+    "Here is the translated text",
+    "Here's the translated text",
+    "Translated text",
+    "Translation",
+    "以下が翻訳です",
+    "以下は翻訳です",
+    "翻訳結果",
+    "翻訳",
+    "訳文",
+];
+
+/// 前後をまとめて取り除く引用符の組
+const QUOTE_PAIRS: [(char, char); 7] = [
+    ('"', '"'),
+    ('\'', '\''),
+    ('“', '”'),
+    ('‘', '’'),
+    ('「', '」'),
+    ('『', '』'),
+    ('«', '»'),
+];
+
+/// `text` が `prefix` で始まっていれば残りを返す（大文字小文字は区別しない）
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let mut chars = text.char_indices();
+    for expected in prefix.chars() {
+        let (_, actual) = chars.next()?;
+        if !actual.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or("", |(index, _)| &text[index..]))
+}
+
+/// 1行目が前置き（"Here is the translation:" など）なら取り除く
+/// 前置きだけの行は行ごと、"Translation: ..." のように同じ行に訳文が続く場合はコロンまでを取り除く
+fn strip_preamble_line<'a>(text: &'a str, phrases: &[String]) -> &'a str {
+    let body = text.trim_start();
+    let (first_line, rest) = body.split_once('\n').unwrap_or((body, ""));
+    let first_line = first_line.trim_end();
+
+    for phrase in phrases.iter().map(|phrase| phrase.trim()).filter(|phrase| !phrase.is_empty()) {
+        let Some(after) = strip_prefix_ignore_case(first_line, phrase) else {
+            continue;
+        };
+        // 語の途中で一致した場合（"Translations are ..." や "翻訳の手順は..."）は前置きではない
+        if after.starts_with(char::is_alphanumeric) {
+            continue;
+        }
+        let after = after.trim_start();
+        // "Here is the translation in Japanese:" のような言い回しも行末のコロンで前置きとみなす
+        if after.is_empty() || after.ends_with([':', '：']) {
+            return rest.trim_start_matches(['\r', '\n']);
+        }
+        if let Some(inline) = after.strip_prefix([':', '：']) {
+            // `inline` は1行目の末尾部分なので、同じ位置から後続の行も含めて返す
+            return body[first_line.len() - inline.len()..].trim_start();
+        }
+    }
+    text
+}
+
+/// 全体が1組の引用符で囲まれていれば外す（内側に同じ引用符がある場合は複数の引用とみなして残す）
+fn strip_surrounding_quotes(text: &str) -> &str {
+    let trimmed = text.trim();
+    for (open, close) in QUOTE_PAIRS {
+        let Some(inner) = trimmed.strip_prefix(open).and_then(|t| t.strip_suffix(close)) else {
+            continue;
+        };
+        if !inner.is_empty() && !inner.contains(open) && !inner.contains(close) {
+            return inner;
+        }
+    }
+    text
+}
+
+/// モデルが付けた前置き（"Here is the translation:" など）と、全体を囲む引用符を取り除く
+/// `phrases` が空の場合は `DEFAULT_PREAMBLE_PHRASES` を使う
+pub fn strip_preamble(text: &str, phrases: &[String]) -> String {
+    let defaults: Vec<String>;
+    let phrases = if phrases.is_empty() {
+        defaults = DEFAULT_PREAMBLE_PHRASES.iter().map(|phrase| phrase.to_string()).collect();
+        &defaults
+    } else {
+        phrases
+    };
+    strip_surrounding_quotes(strip_preamble_line(text, phrases)).to_string()
+}
+
+/// 訳文の明らかな失敗のしかた
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityFlag {
+    /// 訳文が原文と同じ（翻訳されていない可能性がある）。判定は `is_possibly_untranslated` と同じ
+    IdenticalToInput,
+    /// モデルが翻訳を断った・言い訳をした
+    Refusal,
+}
+
+/// 訳文の冒頭に現れたら翻訳を断ったとみなす言い回し（小文字で比較する）
+const REFUSAL_PREFIXES: [&str; 6] = [
+    "i'm sorry",
+    "i am sorry",
+    "sorry, i",
+    "i cannot",
+    "i can't",
+    "i am unable",
+];
+
+/// 訳文のどこかに含まれていたら翻訳を断ったとみなす言い回し（小文字で比較する）
+const REFUSAL_PHRASES: [&str; 4] = [
+    "as an ai language model",
+    "as an ai model",
+    "翻訳できません",
+    "申し訳ありませんが",
+];
+
+/// 原文と訳文を比べて明らかな失敗を検出する。原文に同じ言い回しがある場合（断り文句自体を訳した場合）は除く
+pub fn detect_quality_issues(source: &str, translated: &str) -> Vec<QualityFlag> {
+    let source = source.trim();
+    let translated = translated.trim();
+    let mut flags = Vec::new();
+    if translated.is_empty() {
+        return flags;
+    }
+
+    if is_possibly_untranslated(source, translated) {
+        flags.push(QualityFlag::IdenticalToInput);
+    }
+
+    let source_lower = source.to_lowercase();
+    let translated_lower = translated.to_lowercase();
+    let refused = REFUSAL_PREFIXES
+        .iter()
+        .any(|prefix| translated_lower.starts_with(prefix) && !source_lower.starts_with(prefix))
+        || REFUSAL_PHRASES
+            .iter()
+            .any(|phrase| translated_lower.contains(phrase) && !source_lower.contains(phrase));
+    if refused {
+        flags.push(QualityFlag::Refusal);
+    }
+    flags
+}
+
+// これより短い原文は、訳文が同じでも翻訳漏れとはみなさない（固有名詞・"OK" など、どの言語でも同じ綴りになりうる）
+const UNTRANSLATED_MIN_CHARS: usize = 12;
+
+/// 訳文が原文と（前後の空白・大文字小文字を無視して）同じなら、翻訳されていない可能性がある
+/// 原文の言語を誤判定した時によく起きるため、UIで言語設定の確認や再試行を促す
+pub fn is_possibly_untranslated(source: &str, translated: &str) -> bool {
+    let source = source.trim();
+    source.chars().count() >= UNTRANSLATED_MIN_CHARS && source.to_lowercase() == translated.trim().to_lowercase()
+}
+
+/// 自己評価の応答から0〜100の数値を読む（"85"、"Confidence: 85/100" など最初の整数）
+pub fn parse_confidence(text: &str) -> Option<u8> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let digits: String = text[start..].chars().take_while(char::is_ascii_digit).collect();
+    digits.parse::<u32>().ok().map(|value| value.min(100) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(text: &str) -> String {
+        strip_preamble(text, &[])
+    }
+
+    #[test]
+    fn strips_preamble_line() {
+        assert_eq!(strip("Here is the translation:\nこんにちは"), "こんにちは");
+        assert_eq!(strip("Sure, here's the translation:\n\nBonjour le monde"), "Bonjour le monde");
+        assert_eq!(strip("Here is the translation in Japanese:\nこんにちは"), "こんにちは");
+        assert_eq!(strip("以下が翻訳です：\nHello"), "Hello");
+        assert_eq!(strip("Translation\nHola"), "Hola");
+    }
+
+    #[test]
+    fn strips_inline_preamble() {
+        assert_eq!(strip("Translation: Bonjour"), "Bonjour");
+        assert_eq!(strip("翻訳：こんにちは\n元気ですか"), "こんにちは\n元気ですか");
+        assert_eq!(strip("translated text: Hallo"), "Hallo");
+    }
+
+    #[test]
+    fn strips_surrounding_quotes() {
+        assert_eq!(strip("Here is the translation:\n\"Hello\""), "Hello");
+        assert_eq!(strip("「こんにちは」"), "こんにちは");
+        // 複数の引用は残す
+        assert_eq!(strip("\"a\" and \"b\""), "\"a\" and \"b\"");
+    }
+
+    #[test]
+    fn keeps_text_that_only_starts_with_a_phrase() {
+        let english = "Translations are listed below:\n- one\n- two";
+        assert_eq!(strip(english), english);
+        let japanese = "翻訳の手順は次のとおりです：\n1. 原文を読む";
+        assert_eq!(strip(japanese), japanese);
+        assert_eq!(strip("Translational research matters."), "Translational research matters.");
+    }
+
+    #[test]
+    fn keeps_text_without_preamble() {
+        assert_eq!(strip("こんにちは"), "こんにちは");
+        assert_eq!(strip("Here is the plan: buy milk"), "Here is the plan: buy milk");
+    }
+
+    #[test]
+    fn uses_custom_phrases_instead_of_defaults() {
+        let phrases = vec!["Output".to_string()];
+        assert_eq!(strip_preamble("Output: Hallo", &phrases), "Hallo");
+        assert_eq!(strip_preamble("Translation: Hallo", &phrases), "Translation: Hallo");
+    }
+
+    #[test]
+    fn identical_flag_matches_possibly_untranslated() {
+        let cases = [
+            ("This sentence was not translated.", "this sentence was NOT translated."),
+            ("  Long enough source text  ", "Long enough source text"),
+            ("OK", "OK"),
+            ("This sentence was translated.", "この文は翻訳されました。"),
+        ];
+        for (source, translated) in cases {
+            let flagged = detect_quality_issues(source, translated).contains(&QualityFlag::IdenticalToInput);
+            assert_eq!(flagged, is_possibly_untranslated(source, translated), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn short_identical_text_is_not_flagged() {
+        // 固有名詞など、どの言語でも同じ綴りになりうる短い原文
+        assert!(detect_quality_issues("Tokyo", "Tokyo").is_empty());
+        assert!(!is_possibly_untranslated("Tokyo", "Tokyo"));
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::settings;
+
+/// トレイアイコンを左クリックした時の動作（右クリックは常にメニュー）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayClickBehavior {
+    /// メインウィンドウの表示・非表示を切り替える
+    #[default]
+    Toggle,
+    /// 表示して前面に出すだけで、隠さない
+    ShowOnly,
+    /// ウィンドウには触れずにメニューを開く（macOSのメニューバーの慣習）
+    MenuOnly,
+}
+
+/// バックエンド側で永続化するユーザー設定（`config.json` の `preferences`）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Preferences {
+    pub always_on_top: bool,
+    /// 前回の翻訳以降にクリップボードが変化していれば、Ctrl+C/Cmd+Cの送信を省略してその内容を使う
+    pub reuse_changed_clipboard: bool,
+    /// 翻訳ショートカット（"Ctrl+Alt+L" 形式）。起動時にこの値で登録する。未保存ならNone
+    pub shortcut: Option<String>,
+    /// メインウィンドウにフォーカスが無い時に翻訳が終わったら、結果の冒頭をネイティブ通知で表示する
+    pub notify_on_complete: bool,
+    /// メインウィンドウを隠した時（閉じるボタン・トレイ・ショートカット）に実行中の翻訳・解説をキャンセルする
+    pub cancel_on_hide: bool,
+    pub tray_click_behavior: TrayClickBehavior,
+    /// インライン翻訳（ウィンドウを出さずに訳文をクリップボードへ書き戻す）のショートカット。未設定ならNone
+    pub inline_shortcut: Option<String>,
+    /// インライン翻訳の翻訳先の言語コード。未設定なら"ja"
+    pub inline_target_lang: Option<String>,
+    /// インライン翻訳の訳文をクリップボードに書き戻した後、Ctrl+V / Cmd+Vを送って選択範囲を訳文で置き換える
+    pub replace_selection: bool,
+    /// ホバー翻訳のショートカット。押している間、カーソルを止めた位置の選択範囲を結果ポップアップに訳す。未設定ならNone
+    pub hover_shortcut: Option<String>,
+    /// 解説ショートカット。未設定ならNone
+    pub explain_shortcut: Option<String>,
+    /// ウィンドウの表示切替ショートカット。未設定ならNone
+    pub toggle_shortcut: Option<String>,
+    /// 翻訳が終わってから結果ポップアップを自動で隠すまでの時間（ミリ秒）。Noneなら自動では隠さない
+    pub popup_auto_hide_ms: Option<u64>,
+    /// ローカルHTTPサーバー（`POST /translate`）を起動するか
+    pub http_server_enabled: bool,
+    /// ローカルHTTPサーバーの待ち受けポート。未保存なら既定値
+    pub http_server_port: Option<u16>,
+}
+
+pub struct PreferencesState(pub Mutex<Preferences>);
+
+pub fn load(app: &tauri::AppHandle) -> Preferences {
+    settings::load_config(app)
+        .map(|config| config.preferences)
+        .unwrap_or_default()
+}
+
+/// 設定を変更して保存する
+pub fn update<F>(app: &tauri::AppHandle, f: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Preferences),
+{
+    let state = app.state::<PreferencesState>();
+    let mut guard = state
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to lock preferences: {}", e))?;
+    f(&mut guard);
+    settings::update(app, |config| {
+        config.preferences = guard.clone();
+        Ok(())
+    })
+}
+
+/// 現在の設定のコピーを返す
+pub fn get(app: &tauri::AppHandle) -> Result<Preferences, String> {
+    let state = app.state::<PreferencesState>();
+    let guard = state
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to lock preferences: {}", e))?;
+    Ok(guard.clone())
+}
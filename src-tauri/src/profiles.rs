@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EndpointProfile {
+    pub name: String,
+    pub provider: String,
+    pub endpoint: String,
+    pub model: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// 保存済みのプロファイルとアクティブなプロファイル名（`config.json` の `profiles`）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ProfileStore {
+    pub active: Option<String>,
+    pub profiles: Vec<EndpointProfile>,
+}
+
+/// アプリを起動せずにプロファイルを読む（CLIのヘッドレス翻訳用。`config_dir` は設定ディレクトリ）
+/// `name` 未指定時はアクティブなプロファイルを返す
+pub fn load_profile_from(config_dir: &Path, name: Option<&str>) -> Result<Option<EndpointProfile>, String> {
+    let store = settings::load_config_from(config_dir)?.profiles;
+    let Some(name) = name.or(store.active.as_deref()) else {
+        return Ok(None);
+    };
+    let name = name.to_string();
+    store
+        .profiles
+        .into_iter()
+        .find(|p| p.name == name)
+        .map(Some)
+        .ok_or_else(|| format!("Profile not found: {}", name))
+}
+
+/// 名前でプロファイルを探す
+pub fn find_profile(app: &tauri::AppHandle, name: &str) -> Result<EndpointProfile, String> {
+    settings::read(app)?
+        .profiles
+        .profiles
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Profile not found: {}", name))
+}
+
+/// 同名のプロファイルがあれば上書きする
+#[tauri::command]
+pub async fn save_profile(app: tauri::AppHandle, profile: EndpointProfile) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("Profile name must not be empty".to_string());
+    }
+
+    settings::update(&app, |config| {
+        let store = &mut config.profiles;
+        match store.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => store.profiles.push(profile),
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn list_profiles(app: tauri::AppHandle) -> Result<Vec<EndpointProfile>, String> {
+    Ok(settings::read(&app)?.profiles.profiles)
+}
+
+#[tauri::command]
+pub async fn delete_profile(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    settings::update(&app, |config| {
+        let store = &mut config.profiles;
+        store.profiles.retain(|p| p.name != name);
+        if store.active.as_deref() == Some(name.as_str()) {
+            store.active = None;
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn get_active_profile(app: tauri::AppHandle) -> Result<Option<EndpointProfile>, String> {
+    let store = settings::read(&app)?.profiles;
+    Ok(store
+        .active
+        .and_then(|active| store.profiles.into_iter().find(|p| p.name == active)))
+}
+
+/// アクティブなプロファイルを切り替える。None で解除
+#[tauri::command]
+pub async fn set_active_profile(app: tauri::AppHandle, name: Option<String>) -> Result<(), String> {
+    settings::update(&app, |config| {
+        let store = &mut config.profiles;
+        if let Some(name) = &name {
+            if !store.profiles.iter().any(|p| &p.name == name) {
+                return Err(format!("Profile not found: {}", name));
+            }
+        }
+        store.active = name;
+        Ok(())
+    })
+}
@@ -0,0 +1,1162 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::{Stream, StreamExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::error::TranslateError;
+use crate::ndjson::LineDecoder;
+use crate::sse::SseDecoder;
+use crate::websocket::WebSocketProvider;
+
+/// キャンセル時に返すエラーメッセージ
+pub const CANCELLED_MESSAGE: &str = "Translation cancelled by user";
+
+/// モデルのアンロードに対応していない場合のエラーメッセージ
+const UNLOAD_NOT_SUPPORTED_MESSAGE: &str = "Unloading models is not supported by this provider";
+
+// 温度未指定時にOpenAI互換APIへ送る値（翻訳向けに低めにする）
+pub(crate) const DEFAULT_OPENAI_TEMPERATURE: f32 = 0.3;
+
+/// 次のチャンクを待つ。`stall_timeout` 以内に届かなければストリームが停止したとみなしてエラーにする
+pub async fn next_chunk<S>(
+    stream: &mut S,
+    stall_timeout: std::time::Duration,
+) -> Result<Option<S::Item>, TranslateError>
+where
+    S: Stream + Unpin,
+{
+    tokio::time::timeout(stall_timeout, stream.next())
+        .await
+        .map_err(|_| TranslateError::Timeout {
+            message: format!("Stream stalled: no data received for {} seconds", stall_timeout.as_secs()),
+        })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaRequest {
+    model: String,
+    prompt: String,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<HashMap<String, serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<serde_json::Value>,
+    /// "json" でJSONのみを出力させる
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaStreamResponse {
+    response: String,
+    done: bool,
+    /// 最後の行にだけ含まれる終了理由（"stop" / "length" など）
+    #[serde(default)]
+    done_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<OpenAIMessage>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<HashMap<String, serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<serde_json::Value>,
+    /// "json" でJSONのみを出力させる
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaChatMessage {
+    content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaChatStreamResponse {
+    message: Option<OllamaChatMessage>,
+    done: bool,
+    #[serde(default)]
+    done_reason: Option<String>,
+}
+
+/// Ollamaのストリーム1行の内容
+struct OllamaLine {
+    content: String,
+    done_reason: Option<String>,
+}
+
+/// Ollamaのストリーム1行からテキストを取り出す（`/api/chat` と `/api/generate` で形式が異なる）
+fn parse_ollama_line(line: &str, chat: bool) -> Option<OllamaLine> {
+    if chat {
+        serde_json::from_str::<OllamaChatStreamResponse>(line)
+            .ok()
+            .map(|parsed| OllamaLine {
+                content: parsed.message.map(|message| message.content).unwrap_or_default(),
+                done_reason: parsed.done_reason,
+            })
+    } else {
+        serde_json::from_str::<OllamaStreamResponse>(line)
+            .ok()
+            .map(|parsed| OllamaLine {
+                content: parsed.response,
+                done_reason: parsed.done_reason,
+            })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIStreamRequest {
+    model: String,
+    messages: Vec<OpenAIMessage>,
+    temperature: f32,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u8>,
+}
+
+// ウォームアップ用の非ストリーミングリクエスト
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAICompletionRequest {
+    model: String,
+    messages: Vec<OpenAIMessage>,
+    max_tokens: u32,
+    stream: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct OpenAIDelta {
+    content: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIStreamChoice {
+    /// `n` で複数の候補を生成させた場合の候補番号
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    delta: OpenAIDelta,
+    /// 一部のサーバーは最後のチャンクで残りの出力を`delta`ではなく`message`に入れて送る
+    #[serde(default)]
+    message: Option<OpenAIResponseMessage>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+impl OpenAIStreamChoice {
+    /// このチャンクで追加されたテキスト。`message.content` はそれまでの出力全体の場合もあるため、`received` の続きだけを返す
+    fn take_content(&mut self, received: &str) -> Option<String> {
+        if let Some(content) = self.delta.content.take() {
+            return Some(content);
+        }
+        let content = self.message.take()?.content?;
+        continuation_of(received, &content).filter(|content| !content.is_empty())
+    }
+}
+
+/// `message.content` のうち、まだ受け取っていない部分を返す
+/// - 受信済みの内容で始まる（それまでの出力全体が入っている）場合は続きだけ
+/// - 空白を除くと受信済みの内容で始まる場合（空白を整えて全体を送り直すサーバー）は、対応する位置より後ろだけ。続きが無ければ捨てる
+/// - どちらでもなければ残りの出力だけが入っているとみなしてそのまま使う
+fn continuation_of(received: &str, content: &str) -> Option<String> {
+    if let Some(rest) = content.strip_prefix(received) {
+        return Some(rest.to_string());
+    }
+    let mut remaining = received.chars().filter(|c| !c.is_whitespace());
+    let mut expected = remaining.next();
+    for (index, c) in content.char_indices() {
+        let Some(next) = expected else {
+            return Some(content[index..].trim_start().to_string());
+        };
+        if c.is_whitespace() {
+            continue;
+        }
+        if c != next {
+            return Some(content.to_string());
+        }
+        expected = remaining.next();
+    }
+    // 受信済みの内容と（空白を除いて）同じか、その途中までしか無い
+    None
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIStreamResponse {
+    choices: Vec<OpenAIStreamChoice>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIResponseMessage {
+    content: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIChoice {
+    message: OpenAIResponseMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+// stream: true を無視するサーバー向けの非ストリーミング応答
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIResponse {
+    choices: Vec<OpenAIChoice>,
+}
+
+// ストリーミング用クライアントの接続確立までの上限
+const STREAMING_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 翻訳・解説・比較のストリーミング用HTTPクライアント
+/// 全体の時間は制限しない（長い翻訳を途中で切らないよう、応答本文は `ProviderConfig::stall_timeout` のチャンク間の監視に任せる）
+/// 圧縮するリバースプロキシ経由でもストリームを読めるよう、gzip / deflate / brotli を自動で展開する
+pub fn streaming_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .connect_timeout(STREAMING_CONNECT_TIMEOUT)
+        .gzip(true)
+        .deflate(true)
+        .brotli(true)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// APIキーが指定されていればBearer認証ヘッダーを付ける
+pub fn with_auth(builder: reqwest::RequestBuilder, api_key: Option<&str>) -> reqwest::RequestBuilder {
+    match api_key.filter(|key| !key.is_empty()) {
+        Some(key) => builder.bearer_auth(key),
+        None => builder,
+    }
+}
+
+/// リクエストごとの追加ヘッダー（社内ゲートウェイの `X-Org-Id` など）を検証して変換する
+/// 名前・値がHTTPヘッダーとして不正（ASCII以外・改行を含むなど）な場合はエラー
+pub fn parse_headers(headers: Option<&HashMap<String, String>>) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers.into_iter().flatten() {
+        let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| format!("Invalid header name: {:?}", name))?;
+        let header_value = HeaderValue::from_str(value.trim())
+            .map_err(|_| format!("Invalid value for header {}: only visible ASCII characters are allowed", name))?;
+        map.insert(header_name, header_value);
+    }
+    Ok(map)
+}
+
+/// エラーレスポンスのボディからサーバーのエラーメッセージを取り出す
+/// Ollama: `{"error": "..."}` / OpenAI互換: `{"error": {"message": "..."}}`
+fn extract_error_message(body: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let error = value.get("error")?;
+    error
+        .as_str()
+        .or_else(|| error.get("message").and_then(|m| m.as_str()))
+        .map(|m| m.to_string())
+}
+
+/// ステータスがエラーの場合、ボディを読んでエラー内容を含むメッセージを返す
+pub async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, TranslateError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let detail = extract_error_message(&body).unwrap_or_else(|| {
+        // JSONでない場合はボディの先頭部分をそのまま表示
+        body.trim().chars().take(200).collect()
+    });
+
+    let message = if detail.is_empty() {
+        format!("API error: {}", status)
+    } else {
+        format!("API error: {}: {}", status, detail)
+    };
+    Err(TranslateError::from_status(status, message))
+}
+
+fn is_event_stream(response: &reqwest::Response) -> bool {
+    // Content-Typeが無い場合は従来通りSSEとして扱う
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("text/event-stream"))
+        .unwrap_or(true)
+}
+
+async fn read_openai_response(response: reqwest::Response) -> Result<StreamOutput, TranslateError> {
+    let parsed = response
+        .json::<OpenAIResponse>()
+        .await
+        .map_err(|e| TranslateError::ParseError {
+            message: format!("Failed to parse response: {}", e),
+        })?;
+
+    let mut choices = parsed.choices.into_iter();
+    let Some(first) = choices.next() else {
+        return Ok(StreamOutput::default());
+    };
+    Ok(StreamOutput {
+        text: first.message.content.unwrap_or_default(),
+        finish_reason: first.finish_reason,
+        alternatives: choices.map(|choice| choice.message.content.unwrap_or_default()).collect(),
+    })
+}
+
+/// `keep_alive: 0` を送ってOllamaにモデルをアンロードさせる（`builder` は認証済みの `/api/generate` へのPOST）
+async fn unload_ollama_model(builder: reqwest::RequestBuilder, model: &str) -> Result<(), TranslateError> {
+    let unload_req = OllamaRequest {
+        model: model.to_string(),
+        prompt: String::new(),
+        stream: false,
+        options: None,
+        keep_alive: Some(serde_json::json!(0)),
+        format: None,
+    };
+
+    let response = builder
+        .json(&unload_req)
+        .send()
+        .await
+        .map_err(|e| TranslateError::from_reqwest("Failed to send request", e))?;
+    check_status(response).await?;
+    Ok(())
+}
+
+/// 翻訳リクエストのキャンセル状態（`cancel_translation` で立てられたIDと通知、一括キャンセルの世代、終了処理中のフラグを共有する）
+#[derive(Clone)]
+pub struct CancelToken {
+    cancelled_id: Arc<AtomicU64>,
+    notify: Arc<Notify>,
+    generation: Arc<AtomicU64>,
+    started_generation: u64,
+    shutting_down: Arc<AtomicBool>,
+    request_id: u64,
+}
+
+impl CancelToken {
+    /// `generation` は作成時の値を覚えておき、後で進められたら（一括キャンセル）キャンセル扱いにする
+    pub fn new(
+        cancelled_id: Arc<AtomicU64>,
+        notify: Arc<Notify>,
+        generation: Arc<AtomicU64>,
+        shutting_down: Arc<AtomicBool>,
+        request_id: u64,
+    ) -> Self {
+        let started_generation = generation.load(Ordering::Relaxed);
+        Self {
+            cancelled_id,
+            notify,
+            generation,
+            started_generation,
+            shutting_down,
+            request_id,
+        }
+    }
+
+    /// このリクエストがキャンセルされたか（request_id が 0 の場合は一括キャンセルとアプリ終了時のみ）
+    pub fn is_cancelled(&self) -> bool {
+        self.shutting_down.load(Ordering::Relaxed)
+            || self.generation.load(Ordering::Relaxed) != self.started_generation
+            || (self.request_id != 0 && self.cancelled_id.load(Ordering::Relaxed) == self.request_id)
+    }
+}
+
+pub(crate) enum NextChunk<T> {
+    Chunk(T),
+    End,
+    // キャンセル通知で起こされた（フラグを再確認する）
+    Woken,
+}
+
+/// 次のチャンクを待つ。キャンセル通知が来たらチャンクを待たずに戻る
+pub(crate) async fn next_chunk_or_cancel<S>(
+    stream: &mut S,
+    stall_timeout: Duration,
+    cancel: Option<&CancelToken>,
+) -> Result<NextChunk<S::Item>, TranslateError>
+where
+    S: Stream + Unpin,
+{
+    let chunk = match cancel {
+        Some(cancel) => tokio::select! {
+            chunk = next_chunk(stream, stall_timeout) => chunk?,
+            _ = cancel.notify.notified() => return Ok(NextChunk::Woken),
+        },
+        None => next_chunk(stream, stall_timeout).await?,
+    };
+    Ok(match chunk {
+        Some(chunk) => NextChunk::Chunk(chunk),
+        None => NextChunk::End,
+    })
+}
+
+/// 全プロバイダー共通の接続設定
+pub struct ProviderConfig {
+    pub client: reqwest::Client,
+    pub endpoint: String,
+    pub model: String,
+    pub api_key: Option<String>,
+    /// チャンクが途絶えたと判断するまでの時間
+    pub stall_timeout: Duration,
+    /// 指定時はストリーミング中にキャンセルを検知して `CANCELLED_MESSAGE` のエラーを返す
+    pub cancel: Option<CancelToken>,
+    /// 生成するトークン数の上限（OpenAI互換は`max_tokens`、Ollamaは`num_predict`として送る）。Noneは無制限
+    pub max_tokens: Option<u32>,
+    /// 生成の温度。Noneの場合、OpenAI互換は`DEFAULT_OPENAI_TEMPERATURE`、Ollamaはモデルの既定値
+    pub temperature: Option<f32>,
+    /// 乱数シード（OpenAI互換は`seed`、Ollamaは`options.seed`）。温度0と組み合わせると同じ入力に同じ出力を返す
+    /// ただし実際に決定的になるかはサーバー側がシードに対応しているかによる
+    pub seed: Option<i64>,
+    /// JSONのみを出力させる（OpenAI互換は`response_format: {"type": "json_object"}`、Ollamaは`format: "json"`）
+    pub json_mode: bool,
+    /// 全リクエストに付ける追加ヘッダー（`parse_headers`で検証済み）
+    pub headers: HeaderMap,
+    /// 生成させる候補の数（OpenAI互換の`n`）。2以上なら最初の候補だけを`emit`し、残りは`StreamOutput.alternatives`に集める
+    /// OpenAI互換以外のプロバイダーは無視する
+    pub choices: Option<u8>,
+}
+
+impl ProviderConfig {
+    /// 認証ヘッダーと追加ヘッダーを付ける
+    fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        with_auth(builder, self.api_key.as_deref()).headers(self.headers.clone())
+    }
+}
+
+/// 生成結果
+#[derive(Debug, Default)]
+pub struct StreamOutput {
+    /// `emit` に渡した全テキスト
+    pub text: String,
+    /// サーバーが返した終了理由（"stop" / "length" など。返さないサーバーではNone）
+    pub finish_reason: Option<String>,
+    /// `choices` で生成させた2つ目以降の候補（`emit` には渡さない）。`n` に対応しないサーバーでは空
+    pub alternatives: Vec<String>,
+}
+
+impl StreamOutput {
+    /// トークン数の上限に達して生成が止まったか
+    pub fn hit_length_limit(&self) -> bool {
+        self.finish_reason.as_deref() == Some("length")
+    }
+}
+
+/// テキスト片を受け取るコールバック。false を返すと生成を打ち切る
+pub type ChunkSink<'a> = dyn FnMut(&str) -> bool + Send + 'a;
+
+/// ストリーミングで生成結果を受け取るモデルのバックエンド
+#[async_trait]
+pub trait Provider: Send + Sync {
+    /// `system` と `prompt` を送り、届いたテキスト片ごとに `emit` を呼ぶ。
+    /// `emit` が false を返したらそこで生成を打ち切る。戻り値は `emit` に渡した全テキストと終了理由
+    async fn stream(
+        &self,
+        prompt: String,
+        system: &str,
+        emit: &mut ChunkSink,
+    ) -> Result<StreamOutput, TranslateError>;
+
+    /// 最小限のリクエストを送ってモデルをメモリに読み込ませる（出力は捨てる）
+    async fn warmup(&self) -> Result<(), TranslateError>;
+
+    /// モデルをメモリ（VRAM）から解放させる。既定ではサーバーが対応していないものとしてエラーを返す
+    async fn unload(&self) -> Result<(), TranslateError> {
+        Err(TranslateError::Other {
+            message: UNLOAD_NOT_SUPPORTED_MESSAGE.to_string(),
+        })
+    }
+}
+
+/// Ollama固有の設定（他プロバイダーでは無視される）
+#[derive(Default)]
+pub struct OllamaSettings {
+    pub use_chat_api: bool,
+    pub options: Option<HashMap<String, serde_json::Value>>,
+    /// キャンセル時にモデルをアンロードしてGPUを解放する
+    pub unload_on_cancel: bool,
+    /// 生成後にモデルをメモリに残す時間（`parse_keep_alive`で変換済みの値）
+    pub keep_alive: Option<serde_json::Value>,
+}
+
+/// Ollama（`/api/generate` または `/api/chat`）
+pub struct OllamaProvider {
+    pub config: ProviderConfig,
+    pub settings: OllamaSettings,
+}
+
+#[async_trait]
+impl Provider for OllamaProvider {
+    async fn stream(
+        &self,
+        prompt: String,
+        system: &str,
+        emit: &mut ChunkSink,
+    ) -> Result<StreamOutput, TranslateError> {
+        let config = &self.config;
+        let settings = &self.settings;
+        let options = ollama_options(settings.options.as_ref(), config);
+        let request_builder = if settings.use_chat_api {
+            config
+                .client
+                .post(format!("{}/api/chat", config.endpoint))
+                .json(&OllamaChatRequest {
+                    model: config.model.clone(),
+                    messages: vec![
+                        OpenAIMessage {
+                            role: "system".to_string(),
+                            content: system.to_string(),
+                        },
+                        OpenAIMessage {
+                            role: "user".to_string(),
+                            content: prompt,
+                        },
+                    ],
+                    stream: true,
+                    options: options.clone(),
+                    keep_alive: settings.keep_alive.clone(),
+                    format: config.json_mode.then(|| "json".to_string()),
+                })
+        } else {
+            config
+                .client
+                .post(format!("{}/api/generate", config.endpoint))
+                .json(&OllamaRequest {
+                    model: config.model.clone(),
+                    prompt,
+                    stream: true,
+                    options: options.clone(),
+                    keep_alive: settings.keep_alive.clone(),
+                    format: config.json_mode.then(|| "json".to_string()),
+                })
+        };
+
+        let response = config
+            .authorize(request_builder)
+            .send()
+            .await
+            .map_err(|e| TranslateError::from_reqwest("Failed to send request", e))?;
+        let response = check_status(response).await?;
+
+        let mut stream = response.bytes_stream();
+        let mut output = StreamOutput::default();
+        let mut decoder = LineDecoder::default();
+
+        'stream: loop {
+            if config.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+                // レスポンスを破棄して接続を切り、Ollama側の生成を止める
+                drop(stream);
+                if settings.unload_on_cancel {
+                    let builder = config.authorize(config.client.post(format!("{}/api/generate", config.endpoint)));
+                    let model = config.model.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let _ = unload_ollama_model(builder, &model).await;
+                    });
+                }
+                return Err(TranslateError::Cancelled {
+                    message: CANCELLED_MESSAGE.to_string(),
+                });
+            }
+
+            let (lines, ended) = match next_chunk_or_cancel(&mut stream, config.stall_timeout, config.cancel.as_ref()).await? {
+                NextChunk::Chunk(chunk) => {
+                    let chunk = chunk.map_err(|e| TranslateError::from_reqwest("Stream error", e))?;
+                    let lines = decoder
+                        .push(&chunk)
+                        .map_err(|message| TranslateError::ParseError { message })?;
+                    (lines, false)
+                }
+                // 改行で終わらなかった最後の行も処理する
+                NextChunk::End => (decoder.finish().into_iter().collect(), true),
+                NextChunk::Woken => continue,
+            };
+
+            for line in lines {
+                if let Some(parsed) = parse_ollama_line(&line, settings.use_chat_api) {
+                    if parsed.done_reason.is_some() {
+                        output.finish_reason = parsed.done_reason;
+                    }
+                    if !parsed.content.is_empty() {
+                        output.text.push_str(&parsed.content);
+                        if !emit(&parsed.content) {
+                            break 'stream;
+                        }
+                    }
+                }
+            }
+
+            if ended {
+                break;
+            }
+        }
+
+        Ok(output)
+    }
+
+    async fn warmup(&self) -> Result<(), TranslateError> {
+        // 空のプロンプトを送るとOllamaはモデルを読み込むだけで生成しない
+        let config = &self.config;
+        let response = config
+            .authorize(config.client.post(format!("{}/api/generate", config.endpoint)))
+            .json(&OllamaRequest {
+                model: config.model.clone(),
+                prompt: String::new(),
+                stream: false,
+                options: None,
+                keep_alive: self.settings.keep_alive.clone(),
+                format: None,
+            })
+            .send()
+            .await
+            .map_err(|e| TranslateError::from_reqwest("Failed to send request", e))?;
+        check_status(response).await?;
+        Ok(())
+    }
+
+    async fn unload(&self) -> Result<(), TranslateError> {
+        let config = &self.config;
+        unload_ollama_model(
+            config.authorize(config.client.post(format!("{}/api/generate", config.endpoint))),
+            &config.model,
+        )
+        .await
+    }
+}
+
+/// LM Studio / OpenAI互換API（`/v1/chat/completions`）
+pub struct OpenAiProvider {
+    pub config: ProviderConfig,
+}
+
+#[async_trait]
+impl Provider for OpenAiProvider {
+    async fn stream(
+        &self,
+        prompt: String,
+        system: &str,
+        emit: &mut ChunkSink,
+    ) -> Result<StreamOutput, TranslateError> {
+        let config = &self.config;
+        let openai_req = OpenAIStreamRequest {
+            model: config.model.clone(),
+            messages: vec![
+                OpenAIMessage {
+                    role: "system".to_string(),
+                    content: system.to_string(),
+                },
+                OpenAIMessage {
+                    role: "user".to_string(),
+                    content: prompt,
+                },
+            ],
+            temperature: config.temperature.unwrap_or(DEFAULT_OPENAI_TEMPERATURE),
+            stream: true,
+            max_tokens: config.max_tokens,
+            seed: config.seed,
+            response_format: config
+                .json_mode
+                .then(|| serde_json::json!({ "type": "json_object" })),
+            n: config.choices.filter(|&n| n > 1),
+        };
+
+        let response = config
+            .authorize(config.client.post(format!("{}/v1/chat/completions", config.endpoint)))
+            .json(&openai_req)
+            .send()
+            .await
+            .map_err(|e| TranslateError::from_reqwest("Failed to send request", e))?;
+        let response = check_status(response).await?;
+
+        if !is_event_stream(&response) {
+            // 非ストリーミング応答は1チャンクとして送信
+            let output = read_openai_response(response).await?;
+            if !output.text.is_empty() {
+                emit(&output.text);
+            }
+            return Ok(output);
+        }
+
+        let mut output = StreamOutput::default();
+
+        let mut stream = response.bytes_stream();
+        let mut decoder = SseDecoder::default();
+
+        'stream: loop {
+            if config.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+                return Err(TranslateError::Cancelled {
+                    message: CANCELLED_MESSAGE.to_string(),
+                });
+            }
+
+            let (events, ended) = match next_chunk_or_cancel(&mut stream, config.stall_timeout, config.cancel.as_ref()).await? {
+                NextChunk::Chunk(chunk) => {
+                    let chunk = chunk.map_err(|e| TranslateError::from_reqwest("Stream error", e))?;
+                    let events = decoder
+                        .push(&chunk)
+                        .map_err(|message| TranslateError::ParseError { message })?;
+                    (events, false)
+                }
+                // 空行で閉じられずに終わった最後のイベントも処理する
+                NextChunk::End => (decoder.finish().into_iter().collect(), true),
+                NextChunk::Woken => continue,
+            };
+
+            for data in events {
+                if data.trim() == "[DONE]" {
+                    break 'stream;
+                }
+
+                if let Ok(parsed) = serde_json::from_str::<OpenAIStreamResponse>(&data) {
+                    for mut choice in parsed.choices {
+                        // 2つ目以降の候補は表示せずに集めるだけ
+                        if choice.index > 0 {
+                            if output.alternatives.len() < choice.index {
+                                output.alternatives.resize(choice.index, String::new());
+                            }
+                            let alternative = &mut output.alternatives[choice.index - 1];
+                            if let Some(content) = choice.take_content(alternative) {
+                                alternative.push_str(&content);
+                            }
+                            continue;
+                        }
+                        if choice.finish_reason.is_some() {
+                            output.finish_reason = choice.finish_reason.take();
+                        }
+                        if let Some(content) = choice.take_content(&output.text) {
+                            output.text.push_str(&content);
+                            if !emit(&content) {
+                                break 'stream;
+                            }
+                        }
+                    }
+                }
+            }
+
+            if ended {
+                break;
+            }
+        }
+
+        Ok(output)
+    }
+
+    async fn warmup(&self) -> Result<(), TranslateError> {
+        // 1トークンだけ生成させてモデルを読み込ませる
+        let config = &self.config;
+        let response = config
+            .authorize(config.client.post(format!("{}/v1/chat/completions", config.endpoint)))
+            .json(&OpenAICompletionRequest {
+                model: config.model.clone(),
+                messages: vec![OpenAIMessage {
+                    role: "user".to_string(),
+                    content: "Hi".to_string(),
+                }],
+                max_tokens: 1,
+                stream: false,
+            })
+            .send()
+            .await
+            .map_err(|e| TranslateError::from_reqwest("Failed to send request", e))?;
+        check_status(response).await?;
+        Ok(())
+    }
+
+    async fn unload(&self) -> Result<(), TranslateError> {
+        // LM StudioのREST API（`/api/v1/models/unload`）。モデル管理APIの無いサーバーは404などを返す
+        let config = &self.config;
+        let response = config
+            .authorize(config.client.post(format!("{}/api/v1/models/unload", config.endpoint)))
+            .json(&serde_json::json!({ "instance_id": config.model }))
+            .send()
+            .await
+            .map_err(|e| TranslateError::from_reqwest("Failed to send request", e))?;
+        match check_status(response).await {
+            Ok(_) => Ok(()),
+            // モデルが無い場合は`ModelNotFound`になるので、ここに来るのはエンドポイント自体が無い場合
+            Err(TranslateError::HttpStatus { code: 404 | 405, .. }) => Err(TranslateError::Other {
+                message: format!("{} (server has no model management API)", UNLOAD_NOT_SUPPORTED_MESSAGE),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Ollamaの`options`に出力トークン数の上限（`num_predict`）・温度・シードを加える（`options`で指定された値より優先）
+fn ollama_options(
+    options: Option<&HashMap<String, serde_json::Value>>,
+    config: &ProviderConfig,
+) -> Option<HashMap<String, serde_json::Value>> {
+    let overrides = [
+        ("num_predict", config.max_tokens.map(|v| serde_json::json!(v))),
+        ("temperature", config.temperature.map(|v| serde_json::json!(v))),
+        ("seed", config.seed.map(|v| serde_json::json!(v))),
+    ];
+    if overrides.iter().all(|(_, value)| value.is_none()) {
+        return options.cloned();
+    }
+    let mut options = options.cloned().unwrap_or_default();
+    for (key, value) in overrides {
+        if let Some(value) = value {
+            options.insert(key.to_string(), value);
+        }
+    }
+    Some(options)
+}
+
+/// リクエストのプロバイダー名に応じた実装を返す（"ollama" / "websocket" 以外はOpenAI互換として扱う）
+pub fn for_name(name: &str, config: ProviderConfig, ollama: OllamaSettings) -> Box<dyn Provider> {
+    match name {
+        "ollama" => Box::new(OllamaProvider {
+            config,
+            settings: ollama,
+        }),
+        "websocket" => Box::new(WebSocketProvider { config }),
+        _ => Box::new(OpenAiProvider { config }),
+    }
+}
+
+/// 温度を指定しなかった場合にプロバイダーが送る値（Noneはモデル・サーバーの既定値に任せる）
+pub fn default_temperature(name: &str) -> Option<f32> {
+    match name {
+        "ollama" | "websocket" => None,
+        _ => Some(DEFAULT_OPENAI_TEMPERATURE),
+    }
+}
+
+/// Ollamaの`keep_alive`を検証して送信用の値に変換する
+/// - "5m" / "1h30m" / "30s" などの期間指定はそのまま文字列で送る
+/// - "-1"（無期限）や "0"（即アンロード）などの整数は秒数として数値で送る
+pub fn parse_keep_alive(value: &str) -> Result<serde_json::Value, String> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<i64>() {
+        return Ok(serde_json::json!(seconds));
+    }
+
+    let invalid = || format!("Invalid keep_alive duration: {}", value);
+    let body = value.strip_prefix('-').unwrap_or(value);
+    if body.is_empty() {
+        return Err(invalid());
+    }
+
+    // Goの time.ParseDuration と同じ「数値+単位」の繰り返し
+    let mut rest = body;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        if number_len == 0 || rest[..number_len].parse::<f64>().is_err() {
+            return Err(invalid());
+        }
+        rest = &rest[number_len..];
+
+        let unit = ["ns", "us", "µs", "ms", "s", "m", "h"]
+            .iter()
+            // "ms" を "m" より先に判定するため長い単位から試す
+            .filter(|unit| rest.starts_with(**unit))
+            .max_by_key(|unit| unit.len())
+            .ok_or_else(invalid)?;
+        rest = &rest[unit.len()..];
+    }
+
+    Ok(serde_json::json!(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1回だけ接続を受け付け、`body` を指定の `Content-Type`（`encoding` 指定時は `Content-Encoding` も）で返すサーバーを立てる
+    async fn serve_once(content_type: &'static str, encoding: Option<&'static str>, body: Vec<u8>) -> String {
+        serve_response("200 OK", content_type, encoding, body).await
+    }
+
+    /// `serve_once` のステータス行（"404 Not Found" など）を指定できる版
+    async fn serve_response(
+        status: &'static str,
+        content_type: &'static str,
+        encoding: Option<&'static str>,
+        body: Vec<u8>,
+    ) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            // リクエストヘッダーとボディを読み終えてから応答する
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..header_end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + content_length {
+                        break;
+                    }
+                }
+                if read == 0 {
+                    break;
+                }
+            }
+            let encoding = encoding
+                .map(|encoding| format!("Content-Encoding: {}\r\n", encoding))
+                .unwrap_or_default();
+            let header = format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                content_type,
+                encoding,
+                body.len()
+            );
+            socket.write_all(header.as_bytes()).await.unwrap();
+            // 接続が切られていても（sinkが途中で打ち切った場合など）テストは失敗させない
+            let _ = socket.write_all(&body).await;
+            let _ = socket.shutdown().await;
+        });
+        format!("http://{}", address)
+    }
+
+    fn gzip(body: &str) -> Vec<u8> {
+        use std::io::Write as _;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn config(endpoint: String) -> ProviderConfig {
+        ProviderConfig {
+            client: streaming_client().unwrap(),
+            endpoint,
+            model: "test-model".to_string(),
+            api_key: None,
+            stall_timeout: Duration::from_secs(5),
+            cancel: None,
+            max_tokens: None,
+            temperature: None,
+            seed: None,
+            json_mode: false,
+            headers: HeaderMap::new(),
+            choices: None,
+        }
+    }
+
+    /// `for_name` で作ったプロバイダーに `body` を返させ、`emit` に届いた断片と結果を返す
+    /// `limit` 個の断片を受け取ったら `emit` が false を返して打ち切る
+    fn stream_from(
+        name: &str,
+        content_type: &'static str,
+        body: &'static str,
+        settings: OllamaSettings,
+        limit: usize,
+    ) -> (Vec<String>, StreamOutput) {
+        tauri::async_runtime::block_on(async {
+            let endpoint = serve_once(content_type, None, body.as_bytes().to_vec()).await;
+            let provider = for_name(name, config(endpoint), settings);
+            let mut received = Vec::new();
+            let output = provider
+                .stream("prompt".to_string(), "system", &mut |chunk: &str| {
+                    received.push(chunk.to_string());
+                    received.len() < limit
+                })
+                .await
+                .unwrap();
+            (received, output)
+        })
+    }
+
+    /// OpenAI互換のストリームを `body` のSSEで受け取り、`emit` に届いた断片と結果を返す
+    fn stream_openai_sse(body: &'static str) -> (Vec<String>, StreamOutput) {
+        stream_from("openai", "text/event-stream", body, OllamaSettings::default(), usize::MAX)
+    }
+
+    const OLLAMA_GENERATE_BODY: &str = concat!(
+        "{\"response\":\"こん\",\"done\":false}\n",
+        "\n",
+        "{\"response\":\"にち\",\"done\":false}\n",
+        "{\"response\":\"は\",\"done\":false}\n",
+        "{\"response\":\"\",\"done\":true,\"done_reason\":\"stop\"}\n",
+    );
+
+    #[test]
+    fn ollama_streams_ndjson_lines_through_sink() {
+        let (received, output) =
+            stream_from("ollama", "application/x-ndjson", OLLAMA_GENERATE_BODY, OllamaSettings::default(), usize::MAX);
+        assert_eq!(received, vec!["こん", "にち", "は"]);
+        assert_eq!(output.text, "こんにちは");
+        assert_eq!(output.finish_reason.as_deref(), Some("stop"));
+    }
+
+    #[test]
+    fn ollama_chat_api_reads_message_content() {
+        let body = concat!(
+            "{\"message\":{\"content\":\"Hello\"},\"done\":false}\n",
+            "{\"message\":{\"content\":\", world\"},\"done\":false}\n",
+            "{\"message\":{\"content\":\"\"},\"done\":true,\"done_reason\":\"length\"}",
+        );
+        let settings = OllamaSettings {
+            use_chat_api: true,
+            ..Default::default()
+        };
+        let (received, output) = stream_from("ollama", "application/x-ndjson", body, settings, usize::MAX);
+        assert_eq!(received, vec!["Hello", ", world"]);
+        assert!(output.hit_length_limit());
+    }
+
+    #[test]
+    fn ollama_sink_returning_false_stops_stream() {
+        let (received, output) =
+            stream_from("ollama", "application/x-ndjson", OLLAMA_GENERATE_BODY, OllamaSettings::default(), 2);
+        assert_eq!(received, vec!["こん", "にち"]);
+        assert_eq!(output.text, "こんにち");
+        assert_eq!(output.finish_reason, None);
+    }
+
+    #[test]
+    fn openai_sink_returning_false_stops_stream() {
+        let body = concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"a\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"b\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"c\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let (received, output) = stream_from("openai", "text/event-stream", body, OllamaSettings::default(), 2);
+        assert_eq!(received, vec!["a", "b"]);
+        assert_eq!(output.text, "ab");
+        assert_eq!(output.finish_reason, None);
+    }
+
+    #[test]
+    fn openai_unload_without_model_api_is_unsupported() {
+        let result = tauri::async_runtime::block_on(async {
+            let endpoint = serve_response("404 Not Found", "text/plain", None, b"Not Found".to_vec()).await;
+            for_name("openai", config(endpoint), OllamaSettings::default()).unload().await
+        });
+        match result {
+            Err(TranslateError::Other { message }) => assert!(message.starts_with(UNLOAD_NOT_SUPPORTED_MESSAGE)),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decodes_gzipped_sse_stream() {
+        let body = concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"こん\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"にちは\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let output = tauri::async_runtime::block_on(async {
+            let endpoint = serve_once("text/event-stream", Some("gzip"), gzip(body)).await;
+            let provider = OpenAiProvider { config: config(endpoint) };
+            let mut received = Vec::new();
+            let output = provider
+                .stream("prompt".to_string(), "system", &mut |chunk: &str| {
+                    received.push(chunk.to_string());
+                    true
+                })
+                .await
+                .unwrap();
+            assert_eq!(received, vec!["こん", "にちは"]);
+            output
+        });
+
+        assert_eq!(output.text, "こんにちは");
+        assert_eq!(output.finish_reason.as_deref(), Some("stop"));
+    }
+
+    #[test]
+    fn length_finish_reason_hits_length_limit() {
+        let (_, output) = stream_openai_sse(concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Partial\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"length\"}]}\n\n",
+            "data: [DONE]\n\n",
+        ));
+        assert_eq!(output.text, "Partial");
+        assert!(output.hit_length_limit());
+    }
+
+    #[test]
+    fn final_message_with_whole_output_is_not_duplicated() {
+        let (received, output) = stream_openai_sse(concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello, \"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"world\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"message\":{\"content\":\"Hello,  world!\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        ));
+        assert_eq!(received, vec!["Hello, ", "world", "!"]);
+        assert_eq!(output.text, "Hello, world!");
+    }
+
+    #[test]
+    fn final_message_with_only_the_rest_is_appended() {
+        let (_, output) = stream_openai_sse(concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"message\":{\"content\":\", world\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        ));
+        assert_eq!(output.text, "Hello, world");
+    }
+
+    #[test]
+    fn continuation_handles_full_trimmed_and_partial_messages() {
+        // それまでの出力全体
+        assert_eq!(continuation_of("Hello", "Hello, world").as_deref(), Some(", world"));
+        // 空白を整えて送り直した全体（続きが無ければ捨てる）
+        assert_eq!(continuation_of("Hello,  world ", "Hello, world"), None);
+        assert_eq!(continuation_of(" こんにちは\n", "こんにちは"), None);
+        assert_eq!(continuation_of("Hello,\nworld", "Hello, world. Bye").as_deref(), Some(". Bye"));
+        // 受け取った途中までしか無い
+        assert_eq!(continuation_of("Hello, world", "Hello"), None);
+        // 残りだけ
+        assert_eq!(continuation_of("Hello", "!").as_deref(), Some("!"));
+    }
+
+    #[test]
+    fn parse_keep_alive_accepts_seconds_and_durations() {
+        assert_eq!(parse_keep_alive("5m"), Ok(serde_json::json!("5m")));
+        assert_eq!(parse_keep_alive("1h30m"), Ok(serde_json::json!("1h30m")));
+        assert_eq!(parse_keep_alive("-1"), Ok(serde_json::json!(-1)));
+        assert_eq!(parse_keep_alive("0"), Ok(serde_json::json!(0)));
+        assert_eq!(parse_keep_alive("10"), Ok(serde_json::json!(10)));
+    }
+
+    #[test]
+    fn parse_keep_alive_rejects_malformed_durations() {
+        for value in ["5x", "m", ""] {
+            assert_eq!(
+                parse_keep_alive(value),
+                Err(format!("Invalid keep_alive duration: {}", value)),
+                "{:?}",
+                value
+            );
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::path::Path;
+use std::process::Command;
+
+use serde::Serialize;
+
+/// 選択テキストをコピーさせるための擬似キー入力が使えるかどうか
+#[derive(Debug, Serialize, Clone)]
+pub struct CopyCapability {
+    pub supported: bool,
+    /// 使用するツール（"powershell" / "osascript" / "xdotool" / "wtype" / "ydotool"）
+    pub method: Option<&'static str>,
+    /// 非対応の理由（UIで手動コピーを案内するため）
+    pub reason: Option<String>,
+}
+
+/// 起動時に判定した結果（セッション中は変わらないため1回だけ調べる）
+pub struct CopyCapabilityState(pub CopyCapability);
+
+impl CopyCapability {
+    fn supported(method: &'static str) -> Self {
+        Self {
+            supported: true,
+            method: Some(method),
+            reason: None,
+        }
+    }
+
+    fn unsupported(reason: &str) -> Self {
+        Self {
+            supported: false,
+            method: None,
+            reason: Some(reason.to_string()),
+        }
+    }
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn command_exists(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| Path::new(&dir).join(name).is_file()))
+        .unwrap_or(false)
+}
+
+/// 現在のプラットフォーム・セッションで擬似的なコピーが使えるか調べる
+pub fn detect() -> CopyCapability {
+    #[cfg(target_os = "windows")]
+    {
+        CopyCapability::supported("powershell")
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        CopyCapability::supported("osascript")
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some()
+            || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland");
+        if wayland {
+            // Waylandではアプリ間のキー送信が制限されるため、対応ツールがある場合のみ
+            if command_exists("wtype") {
+                CopyCapability::supported("wtype")
+            } else if command_exists("ydotool") {
+                CopyCapability::supported("ydotool")
+            } else {
+                CopyCapability::unsupported("Synthetic copy is not available on this Wayland session (install wtype or ydotool)")
+            }
+        } else if command_exists("xdotool") {
+            CopyCapability::supported("xdotool")
+        } else {
+            CopyCapability::unsupported("Synthetic copy requires xdotool on X11")
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        CopyCapability::unsupported("Synthetic copy is not supported on this platform")
+    }
+}
+
+/// 擬似的に送るクリップボード操作のキー
+#[derive(Debug, Clone, Copy)]
+enum ClipboardKey {
+    Copy,
+    Paste,
+}
+
+impl ClipboardKey {
+    fn letter(self) -> &'static str {
+        match self {
+            Self::Copy => "c",
+            Self::Paste => "v",
+        }
+    }
+
+    /// Windowsの仮想キーコード
+    fn virtual_key(self) -> &'static str {
+        match self {
+            Self::Copy => "0x43",
+            Self::Paste => "0x56",
+        }
+    }
+
+    /// Linuxのキーコード（46 = KEY_C, 47 = KEY_V）
+    fn linux_key_code(self) -> &'static str {
+        match self {
+            Self::Copy => "46",
+            Self::Paste => "47",
+        }
+    }
+}
+
+// モディファイアキーを全てリリースしてからCtrl+<キー>を送信する（`{VK}` を仮想キーコードに置き換える）
+const POWERSHELL_SEND_CTRL_KEY: &str = r#"
+                        Add-Type @"
+                        using System;
+                        using System.Runtime.InteropServices;
+                        public class KeyHelper {
+                            [DllImport("user32.dll")]
+                            public static extern void keybd_event(byte bVk, byte bScan, uint dwFlags, UIntPtr dwExtraInfo);
+                            public static void ReleaseModifiers() {
+                                uint KEYUP = 0x0002;
+                                keybd_event(0x10, 0, KEYUP, UIntPtr.Zero);
+                                keybd_event(0x11, 0, KEYUP, UIntPtr.Zero);
+                                keybd_event(0x12, 0, KEYUP, UIntPtr.Zero);
+                                keybd_event(0x5B, 0, KEYUP, UIntPtr.Zero);
+                            }
+                            public static void SendCtrlKey(byte key) {
+                                keybd_event(0x11, 0, 0, UIntPtr.Zero);
+                                keybd_event(key, 0, 0, UIntPtr.Zero);
+                                uint KEYUP = 0x0002;
+                                keybd_event(key, 0, KEYUP, UIntPtr.Zero);
+                                keybd_event(0x11, 0, KEYUP, UIntPtr.Zero);
+                            }
+                        }
+"@
+                        [KeyHelper]::ReleaseModifiers()
+                        [System.Threading.Thread]::Sleep(50)
+                        [KeyHelper]::SendCtrlKey({VK})
+                    "#;
+
+/// Ctrl+<キー> / Cmd+<キー> を最前面のアプリに送る
+fn simulate_key(capability: &CopyCapability, key: ClipboardKey) {
+    let Some(method) = capability.method else {
+        return;
+    };
+
+    let mut command = match method {
+        "powershell" => {
+            let mut command = Command::new("powershell");
+            command.args(["-Command", &POWERSHELL_SEND_CTRL_KEY.replace("{VK}", key.virtual_key())]);
+            command
+        }
+        // AppleScript経由でCmd+<キー>を送信
+        "osascript" => {
+            let mut command = Command::new("osascript");
+            command.args([
+                "-e",
+                &format!(
+                    r#"tell application "System Events" to keystroke "{}" using command down"#,
+                    key.letter()
+                ),
+            ]);
+            command
+        }
+        "xdotool" => {
+            let mut command = Command::new("xdotool");
+            command.args(["key", "--clearmodifiers", &format!("ctrl+{}", key.letter())]);
+            command
+        }
+        "wtype" => {
+            let mut command = Command::new("wtype");
+            command.args(["-M", "ctrl", key.letter(), "-m", "ctrl"]);
+            command
+        }
+        // 29 = KEY_LEFTCTRL
+        "ydotool" => {
+            let code = key.linux_key_code();
+            let mut command = Command::new("ydotool");
+            command.args(["key", "29:1", &format!("{}:1", code), &format!("{}:0", code), "29:0"]);
+            command
+        }
+        _ => return,
+    };
+    let _ = command.output();
+}
+
+/// Ctrl+C / Cmd+C を送って選択中のテキストをクリップボードにコピーさせる
+pub fn simulate_copy(capability: &CopyCapability) {
+    simulate_key(capability, ClipboardKey::Copy);
+}
+
+/// Ctrl+V / Cmd+V を送ってクリップボードの内容を最前面のアプリに貼り付けさせる（選択中のテキストは置き換わる）
+pub fn simulate_paste(capability: &CopyCapability) {
+    simulate_key(capability, ClipboardKey::Paste);
+}
+
+#[tauri::command]
+pub fn detect_copy_capability(state: tauri::State<'_, CopyCapabilityState>) -> CopyCapability {
+    state.0.clone()
+}
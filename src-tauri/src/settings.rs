@@ -0,0 +1,224 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::Manager;
+
+use crate::preferences::Preferences;
+use crate::profiles::ProfileStore;
+use crate::storage;
+
+/// バックエンドの設定（ユーザー設定・エンドポイントプロファイル）をまとめて保存するファイル
+pub const CONFIG_FILE: &str = "config.json";
+
+/// 設定ファイルの現在の形式。形式を変えたら上げて `migrate` に変換を足す
+pub const CONFIG_VERSION: u64 = 2;
+
+// バージョン1で設定を分けて保存していたファイル。`config.json` が無い時に一度だけ取り込む（元のファイルは残す）
+const LEGACY_PREFERENCES_FILE: &str = "preferences.json";
+const LEGACY_PROFILES_FILE: &str = "profiles.json";
+
+/// 設定ファイルの読み書きを直列化するためのロック
+pub struct ConfigLock(pub Mutex<()>);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AppConfig {
+    pub version: u64,
+    pub preferences: Preferences,
+    pub profiles: ProfileStore,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            preferences: Preferences::default(),
+            profiles: ProfileStore::default(),
+        }
+    }
+}
+
+/// 古い形式の設定を現在の形式に変換する
+/// - バージョン1（`version` 無し）: ユーザー設定がトップレベルに並ぶ（旧 `preferences.json` と同じ形）
+/// - バージョン2: ユーザー設定を `preferences`、プロファイルを `profiles` に分けて持つ
+pub fn migrate(mut value: Value) -> Value {
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(1);
+    if version < 2 {
+        value = serde_json::json!({
+            "version": 2,
+            "preferences": value,
+            "profiles": {},
+        });
+    }
+    value
+}
+
+fn parse(value: Value, path: &Path) -> Result<AppConfig, String> {
+    serde_json::from_value(migrate(value)).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// ファイル以外（バックアップなど）から読んだ設定を現在の形式に変換して解析する
+pub fn from_value(value: Value) -> Result<AppConfig, String> {
+    serde_json::from_value(migrate(value)).map_err(|e| format!("Failed to parse config: {}", e))
+}
+
+/// `config.json` を1回だけ読む。ファイルが無ければ `None`、解析できずに退避した場合は `Some(Value::Null)`
+fn read_stored(path: &Path) -> Result<Option<Value>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    storage::read_json(path).map(Some)
+}
+
+/// 壊れた `config.json` を退避したことがあるか（`storage::read_json` が `config.json.corrupt-*` に移す）
+fn has_corrupt_backup(dir: &Path) -> bool {
+    let prefix = format!("{}.corrupt-", CONFIG_FILE);
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+        })
+        .unwrap_or(false)
+}
+
+/// 読み込んだ `config.json` の内容から設定を組み立てる。古い形式なら変換し、ファイルが無ければバージョン1の個別のファイルから組み立てる
+fn from_stored(dir: &Path, stored: Option<Value>) -> Result<AppConfig, String> {
+    let path = dir.join(CONFIG_FILE);
+    match stored {
+        // 壊れていて退避した場合も既定値から始める（古い個別のファイルの内容には戻さない）
+        // 退避した後に保存し直す前の読み込み（CLIなど）も同じ
+        Some(Value::Null) => return Ok(AppConfig::default()),
+        Some(value) => return parse(value, &path),
+        None if has_corrupt_backup(dir) => return Ok(AppConfig::default()),
+        None => {}
+    }
+
+    let legacy_preferences = dir.join(LEGACY_PREFERENCES_FILE);
+    let preferences: Value = storage::read_json(&legacy_preferences)?;
+    let mut value = migrate(if preferences.is_null() { serde_json::json!({}) } else { preferences });
+    let profiles: Value = storage::read_json(&dir.join(LEGACY_PROFILES_FILE))?;
+    if !profiles.is_null() {
+        value["profiles"] = profiles;
+    }
+    parse(value, &legacy_preferences)
+}
+
+/// 設定ディレクトリから設定を読む（`from_stored` を参照）
+/// ファイルは書き換えない（アプリを起動せずに読むCLIと共用）
+pub fn load_config_from(dir: &Path) -> Result<AppConfig, String> {
+    from_stored(dir, read_stored(&dir.join(CONFIG_FILE))?)
+}
+
+fn config_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    let path = storage::config_file(app, CONFIG_FILE)?;
+    path.parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| format!("Invalid config path: {}", path.display()))
+}
+
+/// 設定を読み込む。古い形式だった場合は現在の形式で保存し直す
+pub fn load_config(app: &tauri::AppHandle) -> Result<AppConfig, String> {
+    let dir = config_dir(app)?;
+    let stored = read_stored(&dir.join(CONFIG_FILE))?;
+    let stored_version = stored.as_ref().and_then(|value| value.get("version")).and_then(Value::as_u64);
+    let config = from_stored(&dir, stored)?;
+    if stored_version != Some(CONFIG_VERSION) {
+        save_config(app, &config)?;
+    }
+    Ok(config)
+}
+
+/// 設定を現在の形式で保存する
+pub fn save_config(app: &tauri::AppHandle, config: &AppConfig) -> Result<(), String> {
+    let config = AppConfig {
+        version: CONFIG_VERSION,
+        ..config.clone()
+    };
+    storage::write_json(&storage::config_file(app, CONFIG_FILE)?, &config)
+}
+
+/// ロックを取って設定を読む
+pub fn read(app: &tauri::AppHandle) -> Result<AppConfig, String> {
+    let state = app.state::<ConfigLock>();
+    let _guard = state.0.lock().map_err(|e| format!("Failed to lock config: {}", e))?;
+    load_config(app)
+}
+
+/// ロックを取って設定を読み、`f` で変更して保存する。`f` がエラーを返した場合は保存しない
+pub fn update<F, T>(app: &tauri::AppHandle, f: F) -> Result<T, String>
+where
+    F: FnOnce(&mut AppConfig) -> Result<T, String>,
+{
+    let state = app.state::<ConfigLock>();
+    let _guard = state.0.lock().map_err(|e| format!("Failed to lock config: {}", e))?;
+    let mut config = load_config(app)?;
+    let result = f(&mut config)?;
+    save_config(app, &config)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        std::fs::write(dir.join(name), content).unwrap();
+    }
+
+    #[test]
+    fn migrates_version_1_to_version_2() {
+        let migrated = migrate(serde_json::json!({ "always_on_top": true, "shortcut": "Ctrl+Alt+T" }));
+        assert_eq!(migrated["version"], 2);
+        assert_eq!(migrated["preferences"]["always_on_top"], true);
+        assert_eq!(migrated["preferences"]["shortcut"], "Ctrl+Alt+T");
+        assert_eq!(migrated["profiles"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn keeps_current_version_as_is() {
+        let value = serde_json::json!({ "version": 2, "preferences": { "always_on_top": true }, "profiles": {} });
+        assert_eq!(migrate(value.clone()), value);
+    }
+
+    #[test]
+    fn loads_version_1_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), CONFIG_FILE, r#"{"always_on_top": true, "shortcut": "Ctrl+Alt+T"}"#);
+
+        let config = load_config_from(dir.path()).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert!(config.preferences.always_on_top);
+        assert_eq!(config.preferences.shortcut.as_deref(), Some("Ctrl+Alt+T"));
+    }
+
+    #[test]
+    fn imports_legacy_files_when_config_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), LEGACY_PREFERENCES_FILE, r#"{"always_on_top": true}"#);
+        write(dir.path(), LEGACY_PROFILES_FILE, r#"{"active": "local", "profiles": []}"#);
+
+        let config = load_config_from(dir.path()).unwrap();
+        assert!(config.preferences.always_on_top);
+        assert_eq!(config.profiles.active.as_deref(), Some("local"));
+    }
+
+    #[test]
+    fn corrupt_config_does_not_fall_back_to_legacy_files() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), CONFIG_FILE, r#"{"version": 2, "preferences": {"#);
+        write(dir.path(), LEGACY_PREFERENCES_FILE, r#"{"always_on_top": true}"#);
+        write(dir.path(), LEGACY_PROFILES_FILE, r#"{"active": "local", "profiles": []}"#);
+
+        let config = load_config_from(dir.path()).unwrap();
+        assert!(!config.preferences.always_on_top);
+        assert_eq!(config.profiles.active, None);
+        // 壊れたファイルは退避され、次に読んだ時も古い個別のファイルには戻らない
+        assert!(!dir.path().join(CONFIG_FILE).exists());
+        let config = load_config_from(dir.path()).unwrap();
+        assert!(!config.preferences.always_on_top);
+        assert_eq!(config.profiles.active, None);
+    }
+}
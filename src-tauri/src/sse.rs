@@ -0,0 +1,142 @@
+/// Server-Sent Events のストリームをイベント単位に組み立てるデコーダー
+///
+/// - 行はネットワークのチャンク境界をまたいで届くため、改行まではバイト列のまま保持する
+/// - 1つのイベントは空行で終わり、複数の `data:` 行は改行で連結する
+/// - `:` で始まる行はコメント、`event:` / `id:` / `retry:` は使わないので読み捨てる
+/// - 1行が `MAX_LINE_BYTES` を超えたら、それ以上ため込まずにエラーにする（異常なサーバーでメモリを使い切らないように）
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    // まだ改行が届いていない行の断片
+    partial: Vec<u8>,
+    // `partial` のうち改行が無いと確認済みのバイト数（長い行を受信のたびに先頭から探し直さないように）
+    scanned: usize,
+    // 組み立て中のイベントの `data:` の値
+    data: Vec<String>,
+}
+
+/// 1行の最大バイト数
+pub const MAX_LINE_BYTES: usize = 16 * 1024 * 1024;
+
+impl SseDecoder {
+    /// 受信したバイト列を追加し、完成したイベントの `data` を返す
+    /// 改行の無いまま `MAX_LINE_BYTES` を超えた行があればエラーを返す
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<String>, String> {
+        self.partial.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        while let Some(pos) = self.partial[start + self.scanned..].iter().position(|b| *b == b'\n') {
+            let end = start + self.scanned + pos;
+            self.scanned = 0;
+            check_line_length(end - start)?;
+            let line = String::from_utf8_lossy(&self.partial[start..end]).into_owned();
+            start = end + 1;
+            if let Some(event) = self.process_line(line.strip_suffix('\r').unwrap_or(&line)) {
+                events.push(event);
+            }
+        }
+        // 処理した行はまとめて取り除く（1行ごとに詰め直すと行数に比例して遅くなる）
+        self.partial.drain(..start);
+        self.scanned = self.partial.len();
+        check_line_length(self.partial.len())?;
+        Ok(events)
+    }
+
+    /// ストリーム終了時に、空行で閉じられていない最後のイベントを返す
+    pub fn finish(&mut self) -> Option<String> {
+        self.scanned = 0;
+        if !self.partial.is_empty() {
+            let line = String::from_utf8_lossy(&std::mem::take(&mut self.partial)).into_owned();
+            self.process_line(line.strip_suffix('\r').unwrap_or(&line));
+        }
+        self.dispatch()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<String> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        if field == "data" {
+            self.data.push(value.to_string());
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<String> {
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.data).join("\n"))
+    }
+}
+
+fn check_line_length(len: usize) -> Result<(), String> {
+    if len > MAX_LINE_BYTES {
+        return Err(format!(
+            "SSE line exceeds the maximum length of {} bytes",
+            MAX_LINE_BYTES
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_multiline_data_with_newlines() {
+        let mut decoder = SseDecoder::default();
+        let events = decoder.push(b"data: first\ndata: second\n\n").unwrap();
+        assert_eq!(events, vec!["first\nsecond"]);
+    }
+
+    #[test]
+    fn ignores_comment_lines() {
+        let mut decoder = SseDecoder::default();
+        let events = decoder.push(b": keep-alive\ndata: text\n: another\n\n").unwrap();
+        assert_eq!(events, vec!["text"]);
+    }
+
+    #[test]
+    fn ignores_event_and_id_fields() {
+        let mut decoder = SseDecoder::default();
+        let events = decoder
+            .push(b"event: message\nid: 42\nretry: 1000\ndata: {\"a\":1}\n\n")
+            .unwrap();
+        assert_eq!(events, vec!["{\"a\":1}"]);
+    }
+
+    #[test]
+    fn reassembles_frame_split_across_pushes() {
+        let mut decoder = SseDecoder::default();
+        // 複数バイト文字の途中で分割する
+        let frame = "data: こんにちは\r\n\r\n".as_bytes();
+        let (first, second) = frame.split_at(9);
+        assert!(decoder.push(first).unwrap().is_empty());
+        assert_eq!(decoder.push(second).unwrap(), vec!["こんにちは"]);
+    }
+
+    #[test]
+    fn finish_returns_unterminated_event() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"data: [DONE]").unwrap().is_empty());
+        assert_eq!(decoder.finish().as_deref(), Some("[DONE]"));
+    }
+
+    #[test]
+    fn rejects_line_over_limit() {
+        let mut decoder = SseDecoder::default();
+        let chunk = vec![b'x'; MAX_LINE_BYTES / 2 + 1];
+        assert!(decoder.push(&chunk).is_ok());
+        assert!(decoder.push(&chunk).is_err());
+    }
+}
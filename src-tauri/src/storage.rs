@@ -0,0 +1,146 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{de::DeserializeOwned, Serialize};
+use tauri::Manager;
+
+use crate::notices;
+
+/// アプリデータディレクトリのパスを返す（ディレクトリが無ければ作成）
+pub fn data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    Ok(dir)
+}
+
+/// アプリデータディレクトリ内のファイルパスを返す（ディレクトリが無ければ作成）
+pub fn data_file(app: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    Ok(data_dir(app)?.join(name))
+}
+
+/// アプリ設定ディレクトリ内のファイルパスを返す（ディレクトリが無ければ作成）
+pub fn config_file(app: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app config dir: {}", e))?;
+    Ok(dir.join(name))
+}
+
+/// 壊れたファイルを `<name>.corrupt-<UNIX秒>` に退避する
+fn back_up_corrupt(path: &Path) -> Result<PathBuf, String> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".corrupt-{}", stamp));
+    let backup = PathBuf::from(backup);
+    fs::rename(path, &backup)
+        .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+    Ok(backup)
+}
+
+/// JSONファイルを読み込む。ファイルが存在しない場合はデフォルト値を返す
+/// 書き込み途中のクラッシュなどで解析できない場合は、ファイルを退避してデフォルト値から始め直す
+pub fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                let backup = back_up_corrupt(path)?;
+                notices::report(format!(
+                    "Failed to parse {}: {} (moved to {})",
+                    path.display(),
+                    e,
+                    backup.display()
+                ));
+                Ok(T::default())
+            }
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// JSONとして `write_atomic` で保存する
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    write_atomic(path, &content)
+}
+
+/// 一時ファイルに書いてから置き換えることで、途中で落ちても元のファイルが壊れないようにする
+pub fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    let mut file = fs::File::create(&temp)
+        .map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+    file.write_all(content.as_bytes())
+        .and_then(|()| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+    drop(file);
+
+    fs::rename(&temp, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_atomic_replaces_file_without_leaving_temp() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("translation_prompt.txt");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, "new {text}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new {text}");
+        assert!(!dir.path().join("translation_prompt.txt.tmp").exists());
+    }
+
+    #[test]
+    fn recovers_from_truncated_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        // 書き込み途中で落ちたような、閉じていないJSON
+        fs::write(&path, r#"[{"id":"1","source_text":"hello","translated_text":"こん"#).unwrap();
+
+        let history: Vec<serde_json::Value> = read_json(&path).unwrap();
+        assert!(history.is_empty());
+        assert!(!path.exists());
+
+        let backups: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("history.json.corrupt-"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+    }
+
+    #[test]
+    fn missing_file_reads_as_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let value: Vec<String> = read_json(&dir.path().join("missing.json")).unwrap();
+        assert!(value.is_empty());
+    }
+
+    #[test]
+    fn write_then_read_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("favorites.json");
+        write_json(&path, &vec!["a".to_string(), "b".to_string()]).unwrap();
+
+        let value: Vec<String> = read_json(&path).unwrap();
+        assert_eq!(value, vec!["a", "b"]);
+        assert!(!dir.path().join("favorites.json.tmp").exists());
+    }
+}
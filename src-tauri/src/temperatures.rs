@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{provider, storage};
+
+/// モデル名のパターン → 既定の温度の上書きを保存するファイル
+pub const MODEL_TEMPERATURES_FILE: &str = "model_temperatures.json";
+
+/// 組み込みの既定値（モデル名に含まれる文字列 → 温度）。推論モデルは低めの温度を推奨されることが多い
+const BUILTIN_MODEL_TEMPERATURES: [(&str, f32); 2] = [("r1", 0.2), ("qwq", 0.2)];
+
+/// アプリを起動せずに上書き設定を読む（CLIのヘッドレス翻訳用）
+pub fn load_overrides_from(path: &Path) -> HashMap<String, f32> {
+    storage::read_json(path).unwrap_or_default()
+}
+
+/// 上書き設定を読み込む。ファイルが無ければ空
+pub fn load_overrides(app: &tauri::AppHandle) -> HashMap<String, f32> {
+    storage::config_file(app, MODEL_TEMPERATURES_FILE)
+        .map(|path| load_overrides_from(&path))
+        .unwrap_or_default()
+}
+
+/// 組み込みの既定値に上書き設定を重ねた表
+fn table(overrides: &HashMap<String, f32>) -> HashMap<String, f32> {
+    let mut table: HashMap<String, f32> = BUILTIN_MODEL_TEMPERATURES
+        .iter()
+        .map(|(pattern, temperature)| (pattern.to_string(), *temperature))
+        .collect();
+    table.extend(overrides.iter().map(|(pattern, temperature)| (pattern.to_lowercase(), *temperature)));
+    table
+}
+
+/// モデル名に含まれるパターンの温度を返す（大文字小文字は区別しない）。複数一致した場合は長いパターンを優先する
+fn for_model(model: &str, overrides: &HashMap<String, f32>) -> Option<f32> {
+    let model = model.to_lowercase();
+    table(overrides)
+        .into_iter()
+        .filter(|(pattern, _)| !pattern.is_empty() && model.contains(pattern.as_str()))
+        .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+        .map(|(_, temperature)| temperature)
+}
+
+/// 実際に使う温度。指定があればそれを、無ければモデル名の表、それも無ければプロバイダーの既定値
+/// Noneはサーバー側（モデル）の既定値に任せることを表す
+pub fn resolve(
+    provider_name: &str,
+    model: &str,
+    requested: Option<f32>,
+    overrides: &HashMap<String, f32>,
+) -> Option<f32> {
+    requested
+        .or_else(|| for_model(model, overrides))
+        .or_else(|| provider::default_temperature(provider_name))
+}
+
+/// 組み込みの既定値に上書き設定を重ねた表を返す
+#[tauri::command]
+pub async fn get_model_temperatures(app: tauri::AppHandle) -> Result<HashMap<String, f32>, String> {
+    Ok(table(&load_overrides(&app)))
+}
+
+/// モデル名のパターンの既定の温度を保存する。None の場合は上書きを削除して組み込みの値（無ければプロバイダーの既定値）に戻す
+#[tauri::command]
+pub async fn set_model_temperature(
+    app: tauri::AppHandle,
+    pattern: String,
+    temperature: Option<f32>,
+) -> Result<(), String> {
+    let pattern = pattern.trim().to_lowercase();
+    if pattern.is_empty() {
+        return Err("Model pattern is required".to_string());
+    }
+    if let Some(temperature) = temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(format!("Temperature must be between 0 and 2: {}", temperature));
+        }
+    }
+
+    let path = storage::config_file(&app, MODEL_TEMPERATURES_FILE)?;
+    let mut overrides = load_overrides_from(&path);
+    match temperature {
+        Some(temperature) => {
+            overrides.insert(pattern, temperature);
+        }
+        None => {
+            overrides.remove(&pattern);
+        }
+    }
+    storage::write_json(&path, &overrides)
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{languages, storage};
+
+/// 言語ペアごとの追加指示（"ja-en" → 指示文）を保存するファイル
+pub const LANGUAGE_PAIR_PROMPTS_FILE: &str = "language_pair_prompts.json";
+
+/// テンプレートに必須のプレースホルダー
+const REQUIRED_PLACEHOLDERS: [&str; 3] = ["{source}", "{target}", "{text}"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptKind {
+    Translation,
+    Explanation,
+}
+
+impl PromptKind {
+    pub const ALL: [Self; 2] = [Self::Translation, Self::Explanation];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            PromptKind::Translation => "translation_prompt.txt",
+            PromptKind::Explanation => "explanation_prompt.txt",
+        }
+    }
+}
+
+/// 設定ディレクトリからテンプレートを読み込む。存在しない（または空の）場合は None
+pub fn load_template(app: &tauri::AppHandle, kind: PromptKind) -> Option<String> {
+    let path = storage::config_file(app, kind.file_name()).ok()?;
+    let template = fs::read_to_string(path).ok()?;
+    if template.trim().is_empty() {
+        None
+    } else {
+        Some(template)
+    }
+}
+
+pub fn validate_template(template: &str) -> Result<(), String> {
+    let missing: Vec<&str> = REQUIRED_PLACEHOLDERS
+        .iter()
+        .copied()
+        .filter(|placeholder| !template.contains(placeholder))
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Template is missing required placeholders: {}", missing.join(", ")))
+    }
+}
+
+/// プレースホルダーを置換する。`{instructions}` は任意（文体指定などの追加指示）
+pub fn render(template: &str, source: &str, target: &str, instructions: &str, text: &str) -> String {
+    fill_placeholders(
+        template,
+        &[("{source}", source), ("{target}", target), ("{instructions}", instructions), ("{text}", text)],
+    )
+}
+
+/// `{name}` 形式のプレースホルダーを1回の走査で置換する
+/// 置換後の値（原文・訳文）に含まれる `{...}` は、それ自体がプレースホルダーと同じ綴りでも置換されない
+pub fn fill_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match values.iter().find(|(name, _)| rest.starts_with(name)) {
+            Some((name, value)) => {
+                result.push_str(value);
+                rest = &rest[name.len()..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 言語ペアのキー（"ja-en"）を作る。対応言語は英語名で渡されても言語コードに揃える
+pub fn language_pair_key(source: &str, target: &str) -> String {
+    let code = |lang: &str| {
+        languages::find(lang)
+            .map(|found| found.code.to_string())
+            .unwrap_or_else(|| lang.trim().to_lowercase())
+    };
+    format!("{}-{}", code(source), code(target))
+}
+
+/// アプリを起動せずに言語ペアごとの追加指示を読む（CLIのヘッドレス翻訳用）
+pub fn load_language_pair_prompts_from(path: &Path) -> HashMap<String, String> {
+    storage::read_json(path).unwrap_or_default()
+}
+
+/// 言語ペアごとの追加指示を読み込む。ファイルが無ければ空
+pub fn load_language_pair_prompts(app: &tauri::AppHandle) -> HashMap<String, String> {
+    storage::config_file(app, LANGUAGE_PAIR_PROMPTS_FILE)
+        .map(|path| load_language_pair_prompts_from(&path))
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_language_pair_prompts(app: tauri::AppHandle) -> Result<HashMap<String, String>, String> {
+    Ok(load_language_pair_prompts(&app))
+}
+
+/// 言語ペア（"ja-en" 形式）の追加指示を保存する。None または空文字の場合は削除して汎用のプロンプトに戻す
+#[tauri::command]
+pub async fn set_language_pair_prompt(
+    app: tauri::AppHandle,
+    pair: String,
+    prompt: Option<String>,
+) -> Result<(), String> {
+    let key = match pair.split_once('-') {
+        Some((source, target))
+            if !source.trim().is_empty() && !target.trim().is_empty() && source != languages::AUTO =>
+        {
+            language_pair_key(source, target)
+        }
+        _ => return Err(format!("Invalid language pair: {}", pair)),
+    };
+
+    let path = storage::config_file(&app, LANGUAGE_PAIR_PROMPTS_FILE)?;
+    let mut prompts = load_language_pair_prompts_from(&path);
+    match prompt.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) {
+        Some(prompt) => {
+            prompts.insert(key, prompt);
+        }
+        None => {
+            prompts.remove(&key);
+        }
+    }
+    storage::write_json(&path, &prompts)
+}
+
+#[tauri::command]
+pub async fn get_prompt_template(
+    app: tauri::AppHandle,
+    kind: PromptKind,
+) -> Result<Option<String>, String> {
+    Ok(load_template(&app, kind))
+}
+
+/// テンプレートを保存する。None または空文字の場合はファイルを削除して既定のプロンプトに戻す
+#[tauri::command]
+pub async fn set_prompt_template(
+    app: tauri::AppHandle,
+    kind: PromptKind,
+    template: Option<String>,
+) -> Result<(), String> {
+    let path = storage::config_file(&app, kind.file_name())?;
+
+    match template.filter(|t| !t.trim().is_empty()) {
+        Some(template) => {
+            validate_template(&template)?;
+            storage::write_atomic(&path, &template)
+        }
+        None => match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_all_placeholders() {
+        let rendered = render("{source}→{target}\n{instructions}\n{text}", "English", "Japanese", "Be polite.", "Hi");
+        assert_eq!(rendered, "English→Japanese\nBe polite.\nHi");
+    }
+
+    #[test]
+    fn does_not_substitute_inside_values() {
+        let rendered = fill_placeholders(
+            "Source:\n{text}\nTranslation:\n{translation}",
+            &[("{translation}", "訳文 {text}"), ("{text}", "原文 {translation}")],
+        );
+        assert_eq!(rendered, "Source:\n原文 {translation}\nTranslation:\n訳文 {text}");
+    }
+
+    #[test]
+    fn keeps_unknown_braces() {
+        assert_eq!(fill_placeholders("{a} {b} {", &[("{a}", "x")]), "x {b} {");
+    }
+}
@@ -0,0 +1,204 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{languages, provider};
+
+// `translation-throughput` を発行する間隔
+const THROUGHPUT_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+// `translation-progress` を発行する間隔（1秒に約4回）
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_millis(250);
+// 完了前に推定の進捗が100%に達しないよう抑える上限
+const MAX_ESTIMATED_PERCENT: f32 = 99.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EstimateTokensRequest {
+    pub text: String,
+    #[serde(default)]
+    pub provider: String,
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenEstimate {
+    pub tokens: usize,
+    /// モデルのトークナイザーで数えた場合 true、文字数からの概算の場合 false
+    pub exact: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaEmbedResponse {
+    prompt_eval_count: Option<usize>,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'   // ひらがな・カタカナ
+        | '\u{3400}'..='\u{4dbf}' // CJK統合漢字拡張A
+        | '\u{4e00}'..='\u{9fff}' // CJK統合漢字
+        | '\u{ac00}'..='\u{d7af}' // ハングル
+        | '\u{f900}'..='\u{faff}' // CJK互換漢字
+        | '\u{ff00}'..='\u{ffef}' // 全角英数・半角カナ
+    )
+}
+
+/// 端数を切り上げる前の概算トークン数（ストリーミングの小さなチャンクを足し合わせる用）
+fn heuristic_token_weight(text: &str) -> f64 {
+    let (cjk, other) = text.chars().fold((0usize, 0usize), |(cjk, other), c| {
+        if is_cjk(c) {
+            (cjk + 1, other)
+        } else {
+            (cjk, other + 1)
+        }
+    });
+    cjk as f64 / 1.5 + other as f64 / 4.0
+}
+
+/// 文字数からの概算（ラテン文字は約4文字、CJKは約1.5文字で1トークン）
+pub fn heuristic_token_count(text: &str) -> usize {
+    heuristic_token_weight(text).ceil() as usize
+}
+
+/// ストリーミング中の生成速度（トークン/秒）を概算する
+/// プロンプト処理の待ち時間を含めないよう、最初のチャンクが届いてからの時間とそれ以降のトークン数で測る
+#[derive(Default)]
+pub struct ThroughputMeter {
+    started: Option<Instant>,
+    last_chunk: Option<Instant>,
+    last_report: Option<Instant>,
+    tokens: f64,
+}
+
+impl ThroughputMeter {
+    /// 届いたテキストを記録し、前回の報告から1秒以上経っていれば現在の速度を返す
+    pub fn push(&mut self, text: &str) -> Option<f32> {
+        let now = Instant::now();
+        let Some(started) = self.started else {
+            self.started = Some(now);
+            self.last_report = Some(now);
+            return None;
+        };
+        self.tokens += heuristic_token_weight(text);
+        self.last_chunk = Some(now);
+
+        if self.last_report.is_some_and(|last| now.duration_since(last) < THROUGHPUT_REPORT_INTERVAL) {
+            return None;
+        }
+        self.last_report = Some(now);
+        Self::rate(self.tokens, now.duration_since(started))
+    }
+
+    /// 全体の平均速度（チャンクが2つ未満で測れない場合はNone）
+    pub fn tokens_per_second(&self) -> Option<f32> {
+        Self::rate(self.tokens, self.last_chunk?.duration_since(self.started?))
+    }
+
+    fn rate(tokens: f64, elapsed: Duration) -> Option<f32> {
+        let seconds = elapsed.as_secs_f64();
+        (tokens > 0.0 && seconds > 0.0).then(|| (tokens / seconds) as f32)
+    }
+}
+
+/// 原文1文字あたりの訳文の文字数の目安。漢字・かな・ハングルは1文字の情報量が多いため、CJKとそれ以外の間で大きく変わる
+fn expansion_ratio(source_cjk: bool, target_cjk: bool) -> f64 {
+    match (source_cjk, target_cjk) {
+        (true, true) => 1.0,
+        (true, false) => 2.5,
+        (false, true) => 0.4,
+        (false, false) => 1.1,
+    }
+}
+
+/// ストリーミング中の訳文の文字数と、原文の長さから推定した進捗率を報告する
+pub struct ProgressMeter {
+    expected_chars: Option<f64>,
+    chars: usize,
+    last_report: Option<Instant>,
+}
+
+impl ProgressMeter {
+    /// 原文の文字種（原文の言語が自動検出でも判定できるよう文字から数える）と翻訳先の言語から、訳文の長さを見積もる
+    pub fn new(source_text: &str, target_lang: &str) -> Self {
+        let (cjk, total) = source_text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .fold((0usize, 0usize), |(cjk, total), c| (cjk + usize::from(is_cjk(c)), total + 1));
+        let target_cjk = matches!(languages::find(target_lang).map(|lang| lang.code), Some("ja" | "zh" | "ko"));
+        let expected_chars = (total > 0)
+            .then(|| source_text.chars().count() as f64 * expansion_ratio(cjk * 2 > total, target_cjk));
+        Self {
+            expected_chars,
+            chars: 0,
+            last_report: None,
+        }
+    }
+
+    /// 届いたテキストを記録し、前回の報告から`PROGRESS_REPORT_INTERVAL`以上経っていれば（文字数, 推定進捗率%）を返す
+    pub fn push(&mut self, text: &str) -> Option<(usize, Option<f32>)> {
+        self.chars += text.chars().count();
+
+        let now = Instant::now();
+        if self.last_report.is_some_and(|last| now.duration_since(last) < PROGRESS_REPORT_INTERVAL) {
+            return None;
+        }
+        self.last_report = Some(now);
+        let percent = self
+            .expected_chars
+            .map(|expected| ((self.chars as f64 / expected * 100.0) as f32).min(MAX_ESTIMATED_PERCENT));
+        Some((self.chars, percent))
+    }
+}
+
+/// Ollamaの`/api/embed`はトークン数（`prompt_eval_count`）を返すため、それで実際の値を数える
+async fn ollama_token_count(request: &EstimateTokensRequest) -> Result<usize, String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let response = provider::with_auth(
+        client.post(format!("{}/api/embed", request.endpoint)),
+        request.api_key.as_deref(),
+    )
+    .json(&OllamaEmbedRequest {
+        model: &request.model,
+        input: &request.text,
+    })
+    .send()
+    .await
+    .map_err(|e| format!("Failed to send request: {}", e))?;
+    let response = provider::check_status(response).await?;
+
+    response
+        .json::<OllamaEmbedResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?
+        .prompt_eval_count
+        .ok_or_else(|| "Response did not include a token count".to_string())
+}
+
+/// 送信前にトークン数を見積もる。Ollamaで数えられない場合（埋め込み非対応のモデルなど）は概算を返す
+#[tauri::command]
+pub async fn estimate_tokens(request: EstimateTokensRequest) -> Result<TokenEstimate, String> {
+    if request.provider == "ollama" && !request.model.is_empty() {
+        if let Ok(tokens) = ollama_token_count(&request).await {
+            return Ok(TokenEstimate { tokens, exact: true });
+        }
+    }
+
+    Ok(TokenEstimate {
+        tokens: heuristic_token_count(&request.text),
+        exact: false,
+    })
+}
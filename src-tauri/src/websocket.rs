@@ -0,0 +1,197 @@
+use async_trait::async_trait;
+use futures_util::SinkExt;
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderValue, AUTHORIZATION};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+use crate::error::TranslateError;
+use crate::provider::{
+    next_chunk_or_cancel, ChunkSink, NextChunk, Provider, ProviderConfig, StreamOutput, CANCELLED_MESSAGE,
+};
+
+type Socket = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// 接続直後に送るメッセージ
+#[derive(Debug, Serialize)]
+struct WebSocketRequest<'a> {
+    model: &'a str,
+    system: &'a str,
+    prompt: &'a str,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+}
+
+/// サーバーから届くメッセージ。トークンは `token` / `text` / `content` のいずれかで受け付ける
+#[derive(Debug, Deserialize)]
+struct WebSocketMessage {
+    #[serde(alias = "text", alias = "content")]
+    token: Option<String>,
+    #[serde(default)]
+    done: bool,
+    finish_reason: Option<String>,
+    error: Option<String>,
+}
+
+/// WebSocketでストリーミングする推論サーバー（`ws://` / `wss://`）
+/// プロンプトをJSONで1通送り、届いたトークンのメッセージを順に受け取る。`done` か接続の正常終了で完了とする
+pub struct WebSocketProvider {
+    pub config: ProviderConfig,
+}
+
+impl WebSocketProvider {
+    /// エンドポイントに接続する。認証ヘッダーと追加ヘッダーはハンドシェイクのリクエストに付ける
+    async fn connect(&self) -> Result<Socket, TranslateError> {
+        let config = &self.config;
+        let mut request = config
+            .endpoint
+            .as_str()
+            .into_client_request()
+            .map_err(|e| TranslateError::Other {
+                message: format!("Invalid WebSocket endpoint {}: {}", config.endpoint, e),
+            })?;
+        let headers = request.headers_mut();
+        if let Some(key) = config.api_key.as_deref().filter(|key| !key.is_empty()) {
+            let value = HeaderValue::from_str(&format!("Bearer {}", key))
+                .map_err(|e| format!("Invalid API key: {}", e))?;
+            headers.insert(AUTHORIZATION, value);
+        }
+        headers.extend(config.headers.clone());
+
+        let connect = tokio_tungstenite::connect_async(request);
+        let (socket, _) = tokio::time::timeout(config.stall_timeout, connect)
+            .await
+            .map_err(|_| TranslateError::Timeout {
+                message: format!("Timed out connecting to {}", config.endpoint),
+            })?
+            .map_err(|e| map_error("Failed to connect", e))?;
+        Ok(socket)
+    }
+}
+
+/// tungsteniteのエラーを分類する（ハンドシェイクでのHTTPエラーはステータスを引き継ぐ）
+fn map_error(context: &str, e: tungstenite::Error) -> TranslateError {
+    match e {
+        tungstenite::Error::Http(response) => {
+            let status = response.status();
+            let body = response
+                .body()
+                .as_deref()
+                .map(|body| String::from_utf8_lossy(body).trim().to_string())
+                .filter(|body| !body.is_empty())
+                .unwrap_or_else(|| status.canonical_reason().unwrap_or_default().to_string());
+            TranslateError::HttpStatus {
+                code: status.as_u16(),
+                message: format!("API error: {} {}", status.as_u16(), body),
+            }
+        }
+        tungstenite::Error::Utf8 | tungstenite::Error::Protocol(_) | tungstenite::Error::Capacity(_) => {
+            TranslateError::ParseError {
+                message: format!("{}: {}", context, e),
+            }
+        }
+        e => TranslateError::ConnectionFailed {
+            message: format!("{}: {}", context, e),
+        },
+    }
+}
+
+#[async_trait]
+impl Provider for WebSocketProvider {
+    async fn stream(
+        &self,
+        prompt: String,
+        system: &str,
+        emit: &mut ChunkSink,
+    ) -> Result<StreamOutput, TranslateError> {
+        let config = &self.config;
+        let mut socket = self.connect().await?;
+
+        let request = serde_json::to_string(&WebSocketRequest {
+            model: &config.model,
+            system,
+            prompt: &prompt,
+            stream: true,
+            max_tokens: config.max_tokens,
+            temperature: config.temperature,
+            seed: config.seed,
+        })
+        .map_err(|e| format!("Failed to serialize request: {}", e))?;
+        socket
+            .send(Message::text(request))
+            .await
+            .map_err(|e| map_error("Failed to send request", e))?;
+
+        let mut output = StreamOutput::default();
+
+        loop {
+            if config.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+                let _ = socket.close(None).await;
+                return Err(TranslateError::Cancelled {
+                    message: CANCELLED_MESSAGE.to_string(),
+                });
+            }
+
+            let message = match next_chunk_or_cancel(&mut socket, config.stall_timeout, config.cancel.as_ref()).await? {
+                NextChunk::Chunk(message) => message.map_err(|e| map_error("Stream error", e))?,
+                NextChunk::End => break,
+                NextChunk::Woken => continue,
+            };
+
+            let data = match message {
+                Message::Text(text) => text.to_string(),
+                Message::Binary(bytes) => String::from_utf8(bytes.to_vec()).map_err(|e| TranslateError::ParseError {
+                    message: format!("Invalid UTF-8 in WebSocket message: {}", e),
+                })?,
+                Message::Close(frame) => match frame {
+                    Some(frame) if !matches!(frame.code, CloseCode::Normal | CloseCode::Away) => {
+                        return Err(TranslateError::ConnectionFailed {
+                            message: format!("Connection closed by server: {} {}", u16::from(frame.code), frame.reason),
+                        });
+                    }
+                    _ => break,
+                },
+                // Ping への応答はライブラリが行う
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => continue,
+            };
+
+            let parsed: WebSocketMessage = serde_json::from_str(&data).map_err(|e| TranslateError::ParseError {
+                message: format!("Failed to parse WebSocket message: {}", e),
+            })?;
+            if let Some(error) = parsed.error {
+                let _ = socket.close(None).await;
+                return Err(TranslateError::Other {
+                    message: format!("API error: {}", error),
+                });
+            }
+            if let Some(token) = parsed.token.filter(|token| !token.is_empty()) {
+                output.text.push_str(&token);
+                if !emit(&token) {
+                    break;
+                }
+            }
+            if parsed.finish_reason.is_some() {
+                output.finish_reason = parsed.finish_reason;
+            }
+            if parsed.done {
+                break;
+            }
+        }
+
+        let _ = socket.close(None).await;
+        Ok(output)
+    }
+
+    async fn warmup(&self) -> Result<(), TranslateError> {
+        // 接続だけ確認する（モデルの読み込みはサーバー側に任せる）
+        let mut socket = self.connect().await?;
+        let _ = socket.close(None).await;
+        Ok(())
+    }
+}
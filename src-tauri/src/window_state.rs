@@ -0,0 +1,139 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+use crate::storage;
+
+const WINDOW_STATE_FILE: &str = "window-state.json";
+
+/// メインウィンドウの位置とサイズ（物理ピクセル）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 移動・リサイズのたびに更新し、閉じる時と終了時にファイルへ保存する
+pub struct CurrentWindowState(pub Mutex<Option<WindowState>>);
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+impl Rect {
+    fn overlap_area(&self, other: &Rect) -> i64 {
+        let left = self.x.max(other.x) as i64;
+        let top = self.y.max(other.y) as i64;
+        let right = (self.x as i64 + self.width as i64).min(other.x as i64 + other.width as i64);
+        let bottom = (self.y as i64 + self.height as i64).min(other.y as i64 + other.height as i64);
+        (right - left).max(0) * (bottom - top).max(0)
+    }
+}
+
+/// 保存された位置を現在接続されているモニター内に収める
+/// 最も重なりの大きいモニター（どれとも重ならなければ先頭のモニター）に合わせる
+fn clamp_to_monitors(state: WindowState, monitors: &[Rect]) -> WindowState {
+    let window = Rect {
+        x: state.x,
+        y: state.y,
+        width: state.width,
+        height: state.height,
+    };
+
+    let Some(monitor) = monitors
+        .iter()
+        .max_by_key(|monitor| monitor.overlap_area(&window))
+        .copied()
+    else {
+        return state;
+    };
+
+    let width = state.width.min(monitor.width);
+    let height = state.height.min(monitor.height);
+    let max_x = monitor.x + (monitor.width - width) as i32;
+    let max_y = monitor.y + (monitor.height - height) as i32;
+
+    WindowState {
+        x: state.x.clamp(monitor.x, max_x),
+        y: state.y.clamp(monitor.y, max_y),
+        width,
+        height,
+    }
+}
+
+/// 現在のウィンドウ位置・サイズをメモリ上のステートに記録する（最小化中は記録しない）
+pub fn track(window: &tauri::Window) {
+    if window.is_minimized().unwrap_or(false) {
+        return;
+    }
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+        return;
+    };
+
+    let state = window.app_handle().state::<CurrentWindowState>();
+    if let Ok(mut guard) = state.0.lock() {
+        *guard = Some(WindowState {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+        });
+    };
+}
+
+/// 記録済みのウィンドウ状態をファイルに保存する
+pub fn save(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<CurrentWindowState>();
+    let current = *state
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to lock window state: {}", e))?;
+
+    match current {
+        Some(current) => storage::write_json(&storage::config_file(app, WINDOW_STATE_FILE)?, &current),
+        None => Ok(()),
+    }
+}
+
+/// 保存されたウィンドウ状態を復元する。取り外したモニター上に保存されていた場合は画面内に移動する
+pub fn restore(window: &WebviewWindow) -> Result<(), String> {
+    let app = window.app_handle();
+    let saved: Option<WindowState> =
+        storage::read_json(&storage::config_file(app, WINDOW_STATE_FILE)?)?;
+    let Some(saved) = saved else {
+        return Ok(());
+    };
+
+    let monitors: Vec<Rect> = window
+        .available_monitors()
+        .map_err(|e| format!("Failed to get monitors: {}", e))?
+        .iter()
+        .map(|monitor| Rect {
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+        })
+        .collect();
+    let restored = clamp_to_monitors(saved, &monitors);
+
+    window
+        .set_size(PhysicalSize::new(restored.width, restored.height))
+        .map_err(|e| format!("Failed to restore window size: {}", e))?;
+    window
+        .set_position(PhysicalPosition::new(restored.x, restored.y))
+        .map_err(|e| format!("Failed to restore window position: {}", e))?;
+
+    let state = app.state::<CurrentWindowState>();
+    if let Ok(mut guard) = state.0.lock() {
+        *guard = Some(restored);
+    };
+    Ok(())
+}
@@ -5,16 +5,93 @@ import Markdown from "react-markdown";
 import "./App.css";
 
 interface Settings {
-  provider: "ollama" | "lmstudio";
+  provider: "ollama" | "lmstudio" | "websocket";
   endpoint: string;
   model: string;
   targetLang: string;
   shortcut: string;
+  // 空文字の場合は解説ショートカットを登録しない
+  explainShortcut: string;
+  resultPopup: boolean;
+  translateOnPaste: boolean;
 }
 
 interface TranslateResponse {
   translated_text: string;
   detected_lang: string | null;
+  truncated: boolean;
+  tokens_per_second: number | null;
+  temperature: number | null;
+  confidence: number | null;
+  quality_flags: ("identical_to_input" | "refusal")[];
+  alternatives: string[];
+  finish_reason: string | null;
+  possibly_untranslated: boolean;
+}
+
+// file-dropped イベントのペイロード
+interface DroppedFile {
+  path: string;
+  file_name: string;
+  kind: "text" | "srt" | "vtt";
+  text: string;
+}
+
+interface SubtitleTranslateResponse {
+  translated_text: string;
+  cues: number;
+}
+
+// translation-throughput イベントのペイロード
+interface TranslationThroughput {
+  id: number;
+  tokens_per_second: number;
+}
+
+// translation-progress イベントのペイロード（percent は原文の長さからの目安）
+interface TranslationProgress {
+  id: number;
+  chars: number;
+  percent: number | null;
+}
+
+// translation-chunk / explanation-chunk イベントのペイロード
+interface StreamChunk {
+  id: number;
+  chunk: string;
+}
+
+// translate / explain などが返す構造化エラー（Rust側の TranslateError）
+type TranslateError =
+  | { kind: "connection_failed"; message: string }
+  | { kind: "timeout"; message: string }
+  | { kind: "http_status"; code: number; message: string }
+  | { kind: "model_not_found"; message: string }
+  | { kind: "parse_error"; message: string }
+  | { kind: "cancelled"; message: string }
+  | { kind: "other"; message: string };
+
+function isTranslateError(e: unknown): e is TranslateError {
+  return typeof e === "object" && e !== null && "kind" in e && "message" in e;
+}
+
+// エラーの種類に応じた表示用メッセージを返す
+function describeError(e: unknown, model: string): string {
+  if (!isTranslateError(e)) return String(e);
+  switch (e.kind) {
+    case "connection_failed":
+      return `サーバーに接続できません。エンドポイントとサーバーの起動状態を確認してください（${e.message}）`;
+    case "timeout":
+      return `応答がタイムアウトしました（${e.message}）`;
+    case "http_status":
+      return `サーバーがエラーを返しました（HTTP ${e.code}）: ${e.message}`;
+    case "model_not_found":
+      return `モデル「${model}」が見つかりません。モデル名を確認するか、ダウンロードしてください`;
+    case "parse_error":
+      return `応答を解析できませんでした（${e.message}）`;
+    default:
+      return e.message;
+  }
 }
 
 interface HistoryItem {
@@ -25,24 +102,46 @@ interface HistoryItem {
   timestamp: number;
 }
 
-const LANGUAGES = [
-  { code: "Japanese", label: "日本語" },
-  { code: "English", label: "English" },
-  { code: "Chinese", label: "中文" },
-  { code: "Korean", label: "한국어" },
-  { code: "French", label: "Français" },
-  { code: "German", label: "Deutsch" },
-  { code: "Spanish", label: "Español" },
+interface Language {
+  code: string;
+  english_name: string;
+  native_name: string;
+}
+
+// バックエンドの supported_languages を取得するまでの初期値
+const FALLBACK_LANGUAGES: Language[] = [
+  { code: "ja", english_name: "Japanese", native_name: "日本語" },
+  { code: "en", english_name: "English", native_name: "English" },
 ];
 
+// 以前の設定は英語名で保存されているため、言語コードに揃える
+function normalizeLangCode(languages: Language[], value: string): string {
+  return languages.find((l) => l.code === value || l.english_name === value)?.code ?? value;
+}
+
 const isMac = navigator.platform.toUpperCase().includes("MAC");
 
 const DEFAULT_SETTINGS: Settings = {
   provider: "ollama",
   endpoint: "http://localhost:11434",
   model: "llama3",
-  targetLang: "Japanese",
+  targetLang: "ja",
   shortcut: isMac ? "Super+Alt+L" : "Ctrl+Alt+L",
+  explainShortcut: "",
+  resultPopup: false,
+  translateOnPaste: true,
+};
+
+const DEFAULT_ENDPOINTS: Record<Settings["provider"], string> = {
+  ollama: "http://localhost:11434",
+  lmstudio: "http://localhost:1234",
+  websocket: "ws://localhost:8765",
+};
+
+const PROVIDER_LABELS: Record<Settings["provider"], string> = {
+  ollama: "Ollama",
+  lmstudio: "LM Studio",
+  websocket: "WebSocket",
 };
 
 function mapKeyToShortcutString(code: string): string | null {
@@ -204,6 +303,12 @@ function App() {
   const [sourceText, setSourceText] = useState("");
   const [translatedText, setTranslatedText] = useState("");
   const [isLoading, setIsLoading] = useState(false);
+  const [droppedSubtitle, setDroppedSubtitle] = useState<DroppedFile | null>(null);
+  // 生成速度（トークン/秒）。生成中は約1秒ごとに更新し、完了時は全体の平均を表示
+  const [tokensPerSecond, setTokensPerSecond] = useState<number | null>(null);
+  const [temperature, setTemperature] = useState<number | null>(null);
+  // 生成中の進捗の目安（%）。完了したら消す
+  const [progressPercent, setProgressPercent] = useState<number | null>(null);
   const [error, setError] = useState<string | null>(null);
   const [showSettings, setShowSettings] = useState(false);
   const [settings, setSettings] = useState<Settings>(() => {
@@ -214,16 +319,29 @@ function App() {
       return DEFAULT_SETTINGS;
     }
   });
-  const [isCapturingShortcut, setIsCapturingShortcut] = useState(false);
+  const [languages, setLanguages] = useState<Language[]>(FALLBACK_LANGUAGES);
+  const targetLanguages = languages.filter((l) => l.code !== "auto");
+  // どちらのショートカットを入力中か
+  const [capturingShortcut, setCapturingShortcut] = useState<"translate" | "explain" | null>(null);
   const [autostartEnabled, setAutostartEnabled] = useState(false);
+  // 結果ポップアップを自動で隠すまでの秒数（空欄なら隠さない）。設定はRust側に保存する
+  const [popupAutoHideSeconds, setPopupAutoHideSeconds] = useState("");
+  const [copySupported, setCopySupported] = useState<boolean | null>(null);
   const [theme, setTheme] = useState<"light" | "dark">(() => {
     return (localStorage.getItem("translator-theme") as "light" | "dark") || "light";
   });
   const [history, setHistory] = useState<HistoryItem[]>(() => {
     try {
       const saved = localStorage.getItem("translator-history");
-      return saved ? JSON.parse(saved) : [];
-    } catch {
+      if (!saved) return [];
+      const parsed = JSON.parse(saved);
+      if (!Array.isArray(parsed)) throw new Error("History is not an array");
+      return parsed;
+    } catch (e) {
+      // 壊れた履歴は上書きされる前に退避してから空で始める
+      const saved = localStorage.getItem("translator-history");
+      if (saved) localStorage.setItem("translator-history-corrupt", saved);
+      console.error("Failed to load history, backed up to translator-history-corrupt:", e);
       return [];
     }
   });
@@ -236,6 +354,7 @@ function App() {
   const [cancelledInfo, setCancelledInfo] = useState<string | null>(null);
   const explanationCacheRef = useRef<{ source: string; explanation: string } | null>(null);
   const translationRequestIdRef = useRef<number>(0);
+  const explanationRequestIdRef = useRef<number>(0);
 
   useEffect(() => {
     document.documentElement.setAttribute("data-theme", theme);
@@ -249,10 +368,44 @@ function App() {
       .catch((e) => console.error("Failed to get autostart status:", e));
   }, []);
 
+  // 結果ポップアップの自動で隠すまでの時間を読み込む
+  useEffect(() => {
+    invoke<number | null>("get_popup_auto_hide")
+      .then((ms) => setPopupAutoHideSeconds(ms ? String(ms / 1000) : ""))
+      .catch((e) => console.error("Failed to get popup auto-hide:", e));
+  }, []);
+
+  // ショートカット時に選択テキストを自動コピーできる環境か確認
+  useEffect(() => {
+    invoke<{ supported: boolean }>("detect_copy_capability")
+      .then((capability) => setCopySupported(capability.supported))
+      .catch((e) => console.error("Failed to detect copy capability:", e));
+  }, []);
+
+  // 対応言語をバックエンドから読み込み、保存済みの言語名をコードに揃える
+  useEffect(() => {
+    invoke<Language[]>("supported_languages")
+      .then((list) => {
+        setLanguages(list);
+        setSettings((prev) => ({ ...prev, targetLang: normalizeLangCode(list, prev.targetLang) }));
+      })
+      .catch((e) => console.error("Failed to load supported languages:", e));
+  }, []);
+
   const toggleTheme = () => {
     setTheme((prev) => (prev === "light" ? "dark" : "light"));
   };
 
+  const savePopupAutoHide = async (value: string) => {
+    const seconds = Number(value);
+    const autoHideMs = value.trim() && seconds > 0 ? Math.round(seconds * 1000) : null;
+    try {
+      await invoke("set_popup_auto_hide", { autoHideMs });
+    } catch (e) {
+      setError(`ポップアップの設定に失敗しました: ${e}`);
+    }
+  };
+
   const toggleAutostart = async () => {
     const newValue = !autostartEnabled;
     try {
@@ -265,6 +418,8 @@ function App() {
 
   // 自動翻訳用のフラグ
   const pendingTranslateRef = useRef(false);
+  // 解説ショートカットで受け取ったテキストは翻訳後に解説も開く
+  const pendingExplainRef = useRef(false);
 
   useEffect(() => {
     localStorage.setItem("translator-settings", JSON.stringify(settings));
@@ -274,17 +429,32 @@ function App() {
     localStorage.setItem("translator-history", JSON.stringify(history));
   }, [history]);
 
+  // トレイメニューの「最近の翻訳」を更新
+  useEffect(() => {
+    invoke("set_recent_translations", {
+      items: history.slice(0, 5).map((item) => ({ id: item.id, source_text: item.sourceText })),
+    }).catch((e) => console.error("Failed to update recent translations:", e));
+  }, [history]);
+
   // 起動時にグローバルショートカットを登録
   useEffect(() => {
-    invoke("update_shortcut", { shortcut: settings.shortcut }).catch((e) =>
-      console.error("Failed to register initial shortcut:", e)
-    );
+    // 翻訳と解説のショートカットはまとめて登録する（重複があればどちらも登録しない）
+    const shortcuts: Record<string, string> = { translate: settings.shortcut };
+    if (settings.explainShortcut) shortcuts.explain = settings.explainShortcut;
+    invoke("register_shortcuts", { shortcuts })
+      .catch((e) => console.error("Failed to register initial shortcuts:", e))
+      // 実際に登録されているショートカットを表示に反映する
+      .then(() => invoke<string | null>("get_current_shortcut"))
+      .then((current) => {
+        if (current) setSettings((prev) => ({ ...prev, shortcut: current }));
+      })
+      .catch((e) => console.error("Failed to get current shortcut:", e));
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
 
   // キャプチャモード中はwindowレベルでキーイベントを監視
   useEffect(() => {
-    if (!isCapturingShortcut) return;
+    if (!capturingShortcut) return;
 
     const handler = (e: KeyboardEvent) => {
       e.preventDefault();
@@ -305,16 +475,20 @@ function App() {
       parts.push(keyName);
       const shortcutStr = parts.join("+");
 
-      setIsCapturingShortcut(false);
+      setCapturingShortcut(null);
 
-      invoke("update_shortcut", { shortcut: shortcutStr })
-        .then(() => setSettings((prev) => ({ ...prev, shortcut: shortcutStr })))
+      const [action, key] =
+        capturingShortcut === "explain"
+          ? (["explain", "explainShortcut"] as const)
+          : (["translate", "shortcut"] as const);
+      invoke("register_shortcuts", { shortcuts: { [action]: shortcutStr } })
+        .then(() => setSettings((prev) => ({ ...prev, [key]: shortcutStr })))
         .catch((err) => setError(`ショートカットの設定に失敗しました: ${err}`));
     };
 
     window.addEventListener("keydown", handler, true);
     return () => window.removeEventListener("keydown", handler, true);
-  }, [isCapturingShortcut]);
+  }, [capturingShortcut]);
 
   const addToHistory = useCallback((sourceText: string, translatedText: string, targetLang: string) => {
     const newItem: HistoryItem = {
@@ -341,10 +515,16 @@ function App() {
     setExplanationError(null);
     explanationCacheRef.current = null;
 
+    if (settings.resultPopup) {
+      invoke("show_result_popup").catch((e) => console.error("Failed to show result popup:", e));
+    }
+
     try {
-      const response = await invoke<TranslateResponse>("translate", {
+      setTokensPerSecond(null);
+      setProgressPercent(null);
+      const response = await invoke<TranslateResponse>("translate_text", {
+        text,
         request: {
-          text: text,
           source_lang: "auto",
           target_lang: settings.targetLang,
           provider: settings.provider,
@@ -353,21 +533,53 @@ function App() {
           request_id: requestId,
         },
       });
+      setTokensPerSecond(response.tokens_per_second);
+      setTemperature(response.temperature);
       // 履歴に追加
       if (response.translated_text.trim()) {
         addToHistory(text, response.translated_text, settings.targetLang);
       }
     } catch (e) {
-      setError(e as string);
+      // キャンセルは translation-cancelled イベント側で表示する
+      if (!(isTranslateError(e) && e.kind === "cancelled")) {
+        setError(describeError(e, settings.model));
+      }
     } finally {
       setIsLoading(false);
+      setProgressPercent(null);
     }
   }, [sourceText, settings, addToHistory]);
 
   // ストリーミングチャンクを受信
   useEffect(() => {
-    const unlisten = listen<string>("translation-chunk", (event) => {
-      setTranslatedText((prev) => prev + event.payload);
+    const unlisten = listen<StreamChunk>("translation-chunk", (event) => {
+      // キャンセル後などに届いた古いリクエストのチャンクは捨てる
+      if (event.payload.id !== translationRequestIdRef.current) return;
+      setTranslatedText((prev) => prev + event.payload.chunk);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 生成速度の途中経過を受信
+  useEffect(() => {
+    const unlisten = listen<TranslationThroughput>("translation-throughput", (event) => {
+      if (event.payload.id !== translationRequestIdRef.current) return;
+      setTokensPerSecond(event.payload.tokens_per_second);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 進捗の目安を受信
+  useEffect(() => {
+    const unlisten = listen<TranslationProgress>("translation-progress", (event) => {
+      if (event.payload.id !== translationRequestIdRef.current) return;
+      setProgressPercent(event.payload.percent);
     });
 
     return () => {
@@ -377,8 +589,9 @@ function App() {
 
   // 解説ストリーミングチャンクを受信
   useEffect(() => {
-    const unlisten = listen<string>("explanation-chunk", (event) => {
-      setExplanationText((prev) => prev + event.payload);
+    const unlisten = listen<StreamChunk>("explanation-chunk", (event) => {
+      if (event.payload.id !== explanationRequestIdRef.current) return;
+      setExplanationText((prev) => prev + event.payload.chunk);
     });
 
     return () => {
@@ -411,6 +624,7 @@ function App() {
       return;
     }
 
+    const requestId = ++explanationRequestIdRef.current;
     setIsExplanationLoading(true);
     setExplanationError(null);
     setExplanationText("");
@@ -424,6 +638,7 @@ function App() {
           provider: settings.provider,
           endpoint: settings.endpoint,
           model: settings.model,
+          request_id: requestId,
         },
       });
       explanationCacheRef.current = {
@@ -431,7 +646,7 @@ function App() {
         explanation: response.explanation,
       };
     } catch (e) {
-      setExplanationError(e as string);
+      setExplanationError(describeError(e, settings.model));
     } finally {
       setIsExplanationLoading(false);
     }
@@ -479,27 +694,137 @@ function App() {
 
   // ホットキーからの選択テキスト受信と自動翻訳
   useEffect(() => {
-    const unlisten = listen<string>("translate-selection", (event) => {
-      const text = event.payload;
+    const handleSelection = (text: string) => {
       if (text && text.trim()) {
         setSourceText(text);
         setShowSettings(false);
         pendingTranslateRef.current = true;
       }
+    };
+    const unlisten = listen<string>("translate-selection", (event) => handleSelection(event.payload));
+    const unlistenExplain = listen<string>("explain-selection", (event) => {
+      pendingExplainRef.current = true;
+      handleSelection(event.payload);
+    });
+    // クリップボードの画像をOCRした結果も同じように翻訳する
+    const unlistenOcr = listen<string>("ocr-selection", (event) => handleSelection(event.payload));
+    const unlistenOcrFailed = listen<string>("ocr-selection-failed", (event) => {
+      setError(`画像の文字認識に失敗しました: ${event.payload}`);
     });
 
     return () => {
       unlisten.then((fn) => fn());
+      unlistenExplain.then((fn) => fn());
+      unlistenOcr.then((fn) => fn());
+      unlistenOcrFailed.then((fn) => fn());
     };
   }, []);
 
+  // バックエンドの失敗（起動時のショートカット登録・HTTPサーバーなど）を表示する。起動中に溜まった分も最初に受け取る
+  useEffect(() => {
+    const showBackendErrors = () => {
+      invoke<string[]>("take_backend_errors")
+        .then((messages) => {
+          if (messages.length > 0) setError(messages.join("\n"));
+        })
+        .catch(() => {});
+    };
+    showBackendErrors();
+    const unlisten = listen("backend-error", showBackendErrors);
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // ドロップされたファイルを受け取る（テキストは通常通り翻訳し、字幕はタイムコードを残して翻訳する）
+  useEffect(() => {
+    const unlisten = listen<DroppedFile>("file-dropped", (event) => {
+      const file = event.payload;
+      setShowSettings(false);
+      if (file.kind !== "text") {
+        setDroppedSubtitle(file);
+      } else if (file.text.trim()) {
+        setSourceText(file.text);
+        pendingTranslateRef.current = true;
+      }
+    });
+    const unlistenFailed = listen<string>("file-drop-failed", (event) => {
+      setError(`ファイルを読み込めませんでした: ${event.payload}`);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+      unlistenFailed.then((fn) => fn());
+    };
+  }, []);
+
+  // ドロップされた字幕ファイルを翻訳し、保存するか確認する
+  useEffect(() => {
+    if (!droppedSubtitle) return;
+    const file = droppedSubtitle;
+    setDroppedSubtitle(null);
+
+    const requestId = ++translationRequestIdRef.current;
+    setSourceText(file.text);
+    setTranslatedText("");
+    setError(null);
+    setIsLoading(true);
+    invoke<SubtitleTranslateResponse>("translate_subtitles", {
+      request: {
+        text: file.text,
+        source_lang: "auto",
+        target_lang: settings.targetLang,
+        provider: settings.provider,
+        endpoint: settings.endpoint,
+        model: settings.model,
+        request_id: requestId,
+      },
+    })
+      .then(async (response) => {
+        setTranslatedText(response.translated_text);
+        if (window.confirm(`${file.file_name} の翻訳を保存しますか？`)) {
+          await invoke("save_translated_file", {
+            sourcePath: file.path,
+            contents: response.translated_text,
+            targetLang: settings.targetLang,
+          });
+        }
+      })
+      .catch((e) => {
+        if (!(isTranslateError(e) && e.kind === "cancelled")) {
+          setError(describeError(e, settings.model));
+        }
+      })
+      .finally(() => setIsLoading(false));
+  }, [droppedSubtitle, settings]);
+
+  // ウィンドウ再作成時に起動引数から渡されたテキストを受け取る
+  useEffect(() => {
+    invoke<string | null>("take_pending_selection")
+      .then((text) => {
+        if (text && text.trim()) {
+          setSourceText(text);
+          pendingTranslateRef.current = true;
+        }
+      })
+      .catch((e) => console.error("Failed to get pending selection:", e));
+  }, []);
+
   // sourceTextが更新されたら自動翻訳を実行
   useEffect(() => {
     if (pendingTranslateRef.current && sourceText.trim()) {
       pendingTranslateRef.current = false;
-      handleTranslate(sourceText);
+      const explainAfter = pendingExplainRef.current;
+      pendingExplainRef.current = false;
+      handleTranslate(sourceText).then(() => {
+        if (explainAfter) {
+          setIsExplanationOpen(true);
+          handleExplain();
+        }
+      });
     }
-  }, [sourceText, handleTranslate]);
+  }, [sourceText, handleTranslate, handleExplain]);
 
   const handlePaste = async () => {
     try {
@@ -527,10 +852,24 @@ function App() {
   const handleSelectHistory = (item: HistoryItem) => {
     setSourceText(item.sourceText);
     setTranslatedText(item.translatedText);
-    setSettings((prev) => ({ ...prev, targetLang: item.targetLang }));
+    setSettings((prev) => ({ ...prev, targetLang: normalizeLangCode(languages, item.targetLang) }));
     setShowHistory(false);
   };
 
+  // トレイメニューから選ばれた履歴を表示
+  useEffect(() => {
+    const unlisten = listen<string>("show-history-item", (event) => {
+      const item = history.find((h) => h.id === event.payload);
+      if (item) {
+        handleSelectHistory(item);
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  });
+
   const handleDeleteHistory = (id: string, e: React.MouseEvent) => {
     e.stopPropagation();
     setHistory((prev) => prev.filter((item) => item.id !== id));
@@ -587,17 +926,18 @@ function App() {
                 <select
                   value={settings.provider}
                   onChange={(e) => {
-                    const provider = e.target.value as "ollama" | "lmstudio";
+                    const provider = e.target.value as Settings["provider"];
                     setSettings({
                       ...settings,
                       provider,
-                      endpoint: provider === "ollama" ? "http://localhost:11434" : "http://localhost:1234",
+                      endpoint: DEFAULT_ENDPOINTS[provider],
                     });
                   }}
                   className="neu-form-select"
                 >
                   <option value="ollama">Ollama</option>
                   <option value="lmstudio">LM Studio</option>
+                  <option value="websocket">WebSocket</option>
                 </select>
                 <span className="neu-form-select-arrow"><ChevronIcon /></span>
               </div>
@@ -632,9 +972,9 @@ function App() {
                   onChange={(e) => setSettings({ ...settings, targetLang: e.target.value })}
                   className="neu-form-select"
                 >
-                  {LANGUAGES.map((lang) => (
+                  {targetLanguages.map((lang) => (
                     <option key={lang.code} value={lang.code}>
-                      {lang.label}
+                      {lang.native_name}
                     </option>
                   ))}
                 </select>
@@ -643,6 +983,50 @@ function App() {
             </div>
           </div>
 
+          <div className="neu-form-group">
+              <label className="neu-form-label">Result Popup</label>
+              <div
+                className={`neu-toggle ${settings.resultPopup ? "neu-toggle-active" : ""}`}
+                onClick={() => setSettings({ ...settings, resultPopup: !settings.resultPopup })}
+                role="switch"
+                aria-checked={settings.resultPopup}
+                tabIndex={0}
+              >
+                <span className="neu-toggle-slider"></span>
+                <span className="neu-toggle-label">
+                  {settings.resultPopup ? "カーソル付近のポップアップにも表示する" : "メインウィンドウのみに表示"}
+                </span>
+              </div>
+              {settings.resultPopup && (
+                <input
+                  type="number"
+                  min="0"
+                  step="0.5"
+                  value={popupAutoHideSeconds}
+                  onChange={(e) => setPopupAutoHideSeconds(e.target.value)}
+                  onBlur={(e) => savePopupAutoHide(e.target.value)}
+                  placeholder="翻訳完了後に自動で隠すまでの秒数（空欄で隠さない）"
+                  className="neu-input"
+                />
+              )}
+            </div>
+
+          <div className="neu-form-group">
+              <label className="neu-form-label">Paste</label>
+              <div
+                className={`neu-toggle ${settings.translateOnPaste ? "neu-toggle-active" : ""}`}
+                onClick={() => setSettings({ ...settings, translateOnPaste: !settings.translateOnPaste })}
+                role="switch"
+                aria-checked={settings.translateOnPaste}
+                tabIndex={0}
+              >
+                <span className="neu-toggle-slider"></span>
+                <span className="neu-toggle-label">
+                  {settings.translateOnPaste ? "貼り付けたらすぐに翻訳する" : "貼り付けても自動では翻訳しない"}
+                </span>
+              </div>
+            </div>
+
           <div className="neu-form-group">
               <label className="neu-form-label">Startup</label>
               <div
@@ -661,14 +1045,14 @@ function App() {
 
           <div className="neu-hint">
             <p className="neu-hint-title">Hotkey</p>
-            {isCapturingShortcut ? (
+            {capturingShortcut === "translate" ? (
               <div className="neu-input neu-shortcut-capture">
                 キーを入力してください...
               </div>
             ) : (
               <div
                 className="neu-shortcut-display"
-                onClick={() => setIsCapturingShortcut(true)}
+                onClick={() => setCapturingShortcut("translate")}
                 role="button"
                 tabIndex={0}
               >
@@ -678,6 +1062,29 @@ function App() {
                 <span className="neu-shortcut-edit-hint">クリックで変更</span>
               </div>
             )}
+            <p className="neu-hint-title">Explain Hotkey</p>
+            {capturingShortcut === "explain" ? (
+              <div className="neu-input neu-shortcut-capture">
+                キーを入力してください...
+              </div>
+            ) : (
+              <div
+                className="neu-shortcut-display"
+                onClick={() => setCapturingShortcut("explain")}
+                role="button"
+                tabIndex={0}
+              >
+                <span className="neu-hint-text">
+                  {settings.explainShortcut ? formatShortcutDisplay(settings.explainShortcut) : "未設定"}
+                </span>
+                <span className="neu-shortcut-edit-hint">クリックで変更</span>
+              </div>
+            )}
+            {copySupported === false && (
+              <p className="neu-hint-text">
+                この環境では選択テキストを自動でコピーできません。翻訳したいテキストを先にコピーしてからショートカットを押してください
+              </p>
+            )}
           </div>
         </div>
       </div>
@@ -733,6 +1140,12 @@ function App() {
           <textarea
             value={sourceText}
             onChange={(e) => setSourceText(e.target.value)}
+            onPaste={(e) => {
+              // 貼り付け後のテキストで翻訳する（onChange で sourceText が更新されてから実行される）
+              if (settings.translateOnPaste && e.clipboardData.getData("text").trim()) {
+                pendingTranslateRef.current = true;
+              }
+            }}
             onKeyDown={handleKeyDown}
             placeholder="Enter text to translate..."
             className="neu-textarea"
@@ -747,9 +1160,9 @@ function App() {
               onChange={(e) => setSettings({ ...settings, targetLang: e.target.value })}
               className="neu-select"
             >
-              {LANGUAGES.map((lang) => (
+              {targetLanguages.map((lang) => (
                 <option key={lang.code} value={lang.code}>
-                  {lang.label}
+                  {lang.native_name}
                 </option>
               ))}
             </select>
@@ -877,7 +1290,10 @@ function App() {
         <footer className="neu-footer">
           <div className="neu-status">
             <span className="neu-status-dot"></span>
-            {settings.provider === "ollama" ? "Ollama" : "LM Studio"} · {settings.model} · {formatShortcutDisplay(settings.shortcut)}
+            {PROVIDER_LABELS[settings.provider]} · {settings.model} · {formatShortcutDisplay(settings.shortcut)}
+            {tokensPerSecond !== null && ` · ${tokensPerSecond.toFixed(1)} t/s`}
+            {temperature !== null && ` · T ${temperature.toFixed(1)}`}
+            {isLoading && progressPercent !== null && ` · ${Math.round(progressPercent)}%`}
           </div>
         </footer>
       </div>
@@ -922,7 +1338,7 @@ function App() {
               >
                 <div className="neu-history-item-header">
                   <span className="neu-history-lang">
-                    → {LANGUAGES.find((l) => l.code === item.targetLang)?.label || item.targetLang}
+                    → {languages.find((l) => l.code === normalizeLangCode(languages, item.targetLang))?.native_name || item.targetLang}
                   </span>
                   <span className="neu-history-time">{formatTimestamp(item.timestamp)}</span>
                 </div>
@@ -0,0 +1,76 @@
+import { useState, useEffect, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import "./App.css";
+
+// カーソル付近に表示する翻訳結果のポップアップ
+function ResultPopup() {
+  const [text, setText] = useState("");
+  // 表示中の翻訳のリクエストID（新しいIDのチャンクが来たら切り替える）
+  const requestIdRef = useRef<number | null>(null);
+
+  // メインウィンドウと同じテーマを適用
+  useEffect(() => {
+    const theme = localStorage.getItem("translator-theme") || "light";
+    document.documentElement.setAttribute("data-theme", theme);
+  }, []);
+
+  // 新しい翻訳の開始時に内容をクリア
+  useEffect(() => {
+    const unlisten = listen("result-popup-reset", () => {
+      requestIdRef.current = null;
+      setText("");
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // ホバー翻訳の結果（ストリーミングせずに訳文全体が届く）
+  useEffect(() => {
+    const unlisten = listen<string>("result-popup-text", (event) => {
+      setText(event.payload);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<{ id: number; chunk: string }>("translation-chunk", (event) => {
+      const { id, chunk } = event.payload;
+      if (requestIdRef.current === null || id > requestIdRef.current) {
+        requestIdRef.current = id;
+        setText(chunk);
+      } else if (id === requestIdRef.current) {
+        setText((prev) => prev + chunk);
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleKeyDown = (e: React.KeyboardEvent) => {
+    if (e.key === "Escape") {
+      invoke("hide_result_popup");
+    }
+  };
+
+  return (
+    <div
+      className="neu-popup"
+      onKeyDown={handleKeyDown}
+      // マウスが乗ったら読んでいるものとして自動では隠さない
+      onMouseEnter={() => invoke("keep_result_popup")}
+      tabIndex={0}
+    >
+      <div className="neu-popup-text">{text || "…"}</div>
+    </div>
+  );
+}
+
+export default ResultPopup;
@@ -1,9 +1,13 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import ResultPopup from "./ResultPopup";
+
+// 結果ポップアップのウィンドウは "#popup" 付きのURLで開かれる
+const isPopup = window.location.hash === "#popup";
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {isPopup ? <ResultPopup /> : <App />}
   </React.StrictMode>,
 );